@@ -0,0 +1,204 @@
+use rzdb::{Data, Db};
+
+use crate::date::Hms;
+use crate::error::Result;
+use crate::format::{self, DisplayFormat};
+use crate::settings;
+
+const AGGREGATE_KEY: &str = "aggregate";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Count,
+    CountEmpty,
+}
+
+impl Aggregate {
+    pub fn parse(s: &str) -> Option<Aggregate> {
+        match s.to_lowercase().replace([' ', '_'], "").as_str() {
+            "sum" => Some(Aggregate::Sum),
+            "avg" | "average" => Some(Aggregate::Avg),
+            "min" => Some(Aggregate::Min),
+            "max" => Some(Aggregate::Max),
+            "count" => Some(Aggregate::Count),
+            "countempty" => Some(Aggregate::CountEmpty),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aggregate::Sum => "sum",
+            Aggregate::Avg => "avg",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+            Aggregate::Count => "count",
+            Aggregate::CountEmpty => "count empty",
+        }
+    }
+
+    fn fold(&self, values: &[f64]) -> Option<f64> {
+        if values.is_empty() {
+            return None;
+        }
+        let sum: f64 = values.iter().sum();
+        match self {
+            Aggregate::Sum => Some(sum),
+            Aggregate::Avg => Some(sum / values.len() as f64),
+            Aggregate::Min => values.iter().copied().reduce(f64::min),
+            Aggregate::Max => values.iter().copied().reduce(f64::max),
+            Aggregate::Count | Aggregate::CountEmpty => None,
+        }
+    }
+
+    // integers are added up exactly, None if the sum overflows
+    fn fold_ints(&self, values: &[i64]) -> Option<i64> {
+        match self {
+            Aggregate::Sum => values
+                .iter()
+                .try_fold(0i64, |sum, value| sum.checked_add(*value)),
+            Aggregate::Min => values.iter().copied().min(),
+            Aggregate::Max => values.iter().copied().max(),
+            _ => None,
+        }
+    }
+
+    // numbers are aggregated if there are any, otherwise times of day
+    pub fn compute(&self, cells: &[Data], format: Option<&DisplayFormat>) -> String {
+        match self {
+            Aggregate::Count => {
+                let count = cells.iter().filter(|c| !matches!(c, Data::Empty)).count();
+                return count.to_string();
+            }
+            Aggregate::CountEmpty => {
+                let count = cells.iter().filter(|c| matches!(c, Data::Empty)).count();
+                return count.to_string();
+            }
+            _ => {}
+        }
+        let ints: Vec<i64> = cells
+            .iter()
+            .filter(|c| matches!(c, Data::Int(_)))
+            .filter_map(|c| c.to_string().parse().ok())
+            .collect();
+        let all_int = cells.iter().all(|c| !matches!(c, Data::Float(_)));
+        if all_int && !ints.is_empty() {
+            if let Some(value) = self.fold_ints(&ints) {
+                return format::display(&Data::parse(&value.to_string()), format);
+            }
+        }
+        // mixed columns, averages and overflowing sums are computed as floats
+        let numbers: Vec<f64> = cells
+            .iter()
+            .filter(|c| format::is_numeric(c))
+            .filter_map(|c| c.to_string().parse().ok())
+            .collect();
+        if !numbers.is_empty() {
+            return match self.fold(&numbers) {
+                Some(value) => {
+                    // round away float noise like 0.30000000000000004
+                    let value = (value * 1e6).round() / 1e6;
+                    format::display(&Data::Float(value), format)
+                }
+                None => "".to_string(),
+            };
+        }
+        let seconds: Vec<f64> = cells
+            .iter()
+            .filter_map(Hms::from_data)
+            .map(|t| f64::from(t.hour * 3600 + t.minute * 60 + t.second))
+            .collect();
+        match self.fold(&seconds) {
+            Some(seconds) => format_duration(seconds.round() as u64),
+            None => "".to_string(),
+        }
+    }
+}
+
+// hours may exceed 24, seconds are only shown if there are any
+fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if seconds == 0 {
+        format!("{}:{:02}", hours, minutes)
+    } else {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    }
+}
+
+// "count 3, sum 12, avg 4, min 1, max 7" of the selected cells, shown in the status line
+pub(crate) fn selection_stats(cells: &[Data]) -> String {
+    let mut stats = format!("count {}", Aggregate::Count.compute(cells, None));
+    for aggregate in [
+        Aggregate::Sum,
+        Aggregate::Avg,
+        Aggregate::Min,
+        Aggregate::Max,
+    ] {
+        let value = aggregate.compute(cells, None);
+        if !value.is_empty() {
+            stats += &format!(", {} {}", aggregate.name(), value);
+        }
+    }
+    stats
+}
+
+// footer aggregate of each column, in column order
+pub(crate) fn get_all(db: &Db, table_name: &str) -> Vec<Option<Aggregate>> {
+    settings::get_for_columns(db, table_name, AGGREGATE_KEY)
+        .iter()
+        .map(|s| s.as_ref().and_then(|s| Aggregate::parse(s)))
+        .collect()
+}
+
+// None removes the aggregate
+pub(crate) fn set(
+    db: &mut Db,
+    table_name: &str,
+    column_name: &str,
+    aggregate: Option<Aggregate>,
+) -> Result<()> {
+    let value = aggregate.map(|a| a.name()).unwrap_or_default();
+    settings::set(db, table_name, column_name, AGGREGATE_KEY, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0:00");
+        assert_eq!(format_duration(90 * 60), "1:30");
+        assert_eq!(format_duration(25 * 3600 + 5), "25:00:05");
+    }
+
+    #[test]
+    fn test_compute() {
+        let cells = |values: &[&str]| values.iter().map(|s| Data::parse(s)).collect::<Vec<_>>();
+        let big = cells(&["9007199254740993", "1", ""]);
+        assert_eq!(Aggregate::Sum.compute(&big, None), "9007199254740994");
+        assert_eq!(Aggregate::Max.compute(&big, None), "9007199254740993");
+        assert_eq!(Aggregate::Sum.compute(&cells(&["1", "2.5"]), None), "3.5");
+        let overflow = cells(&["9223372036854775807", "1"]);
+        // not saturated at i64::MAX
+        assert_ne!(
+            Aggregate::Sum.compute(&overflow, None),
+            "9223372036854775807"
+        );
+    }
+
+    #[test]
+    fn test_parse_aggregate() {
+        assert_eq!(Aggregate::parse("SUM"), Some(Aggregate::Sum));
+        assert_eq!(Aggregate::parse("count empty"), Some(Aggregate::CountEmpty));
+        assert_eq!(
+            Aggregate::parse(Aggregate::CountEmpty.name()),
+            Some(Aggregate::CountEmpty)
+        );
+        assert_eq!(Aggregate::parse("median"), None);
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeSet;
+
+use rzdb::Db;
+
+use crate::column_type::{self, ColumnType};
+use crate::common::is_cell;
+use crate::date::Ymd;
+use crate::editor::Editor;
+use crate::error::Result;
+use crate::mode::Mode;
+use crate::pos::Pos;
+use crate::State;
+
+// while the calendar is open the editor holds the highlighted date,
+// so leaving it with Enter stores the date like typed text
+
+pub(crate) fn is_date_column(db: &Db, state: &State, cursor: &Pos) -> bool {
+    cursor.y > 0
+        && db
+            .get_column_name_at(&state.table_name, cursor.x - 1)
+            .is_ok_and(|column_name| {
+                column_type::get(db, &state.table_name, &column_name) == Some(ColumnType::Date)
+            })
+}
+
+// gc, or editing a date column: start on the date of the cell, today for other cells
+pub(crate) fn open(
+    db: &Db,
+    state: &State,
+    cursor: &Pos,
+    editor: &mut Editor,
+    mode: &mut Mode,
+) -> Result<()> {
+    if cursor.y == 0 {
+        return Err("No calendar for column names".into());
+    }
+    let date = if is_cell(db, state, cursor.x - 1, cursor.y - 1) {
+        Ymd::from_data(&db.select_at(&state.table_name, cursor.x - 1, cursor.y - 1)?)
+    } else {
+        None
+    };
+    set(editor, date.unwrap_or_else(Ymd::today));
+    *mode = Mode::Calendar;
+    Ok(())
+}
+
+pub(crate) fn selected(editor: &Editor) -> Ymd {
+    Ymd::parse(&editor.line).unwrap_or_else(Ymd::today)
+}
+
+pub(crate) fn set(editor: &mut Editor, date: Ymd) {
+    let text = date.format("%Y-%m-%d");
+    editor.insert_at(&text, text.len());
+}
+
+// months first, so that the day is clamped to the target month
+pub(crate) fn shift(editor: &mut Editor, days: i64, months: i64) {
+    let date = selected(editor)
+        .add_months(months)
+        .and_then(|date| date.add_days(days));
+    if let Some(date) = date {
+        set(editor, date);
+    }
+}
+
+// weeks of the month from Monday to Sunday, None for days of the neighbouring months
+pub(crate) fn month_grid(year: i32, month: u32) -> Vec<[Option<Ymd>; 7]> {
+    let mut weeks = vec![];
+    let mut week = [None; 7];
+    let mut day = Ymd::new(year, month, 1);
+    while let Some(date) = day {
+        week[date.weekday() as usize] = Some(date);
+        if date.weekday() == 6 {
+            weeks.push(std::mem::take(&mut week));
+        }
+        day = Ymd::new(year, month, date.day + 1);
+    }
+    if week.iter().any(|date| date.is_some()) {
+        weeks.push(week);
+    }
+    weeks
+}
+
+// dates in column x, marked in the calendar
+pub(crate) fn column_dates(db: &Db, table_name: &str, x: usize) -> BTreeSet<Ymd> {
+    (0..db.get_row_count(table_name).unwrap_or(0))
+        .filter_map(|y| db.select_at(table_name, x, y).ok())
+        .filter_map(|data| Ymd::from_data(&data))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_month_grid() {
+        // March 2023 starts on a Wednesday and ends on a Friday
+        let weeks = month_grid(2023, 3);
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(weeks[0][1], None);
+        assert_eq!(weeks[0][2], Ymd::new(2023, 3, 1));
+        assert_eq!(weeks[4][4], Ymd::new(2023, 3, 31));
+        assert_eq!(weeks[4][5], None);
+        // February 2021 fills exactly four weeks
+        assert_eq!(month_grid(2021, 2).len(), 4);
+    }
+}
@@ -0,0 +1,103 @@
+use rzdb::{Data, Db};
+
+use crate::column_type::{self, parse_bool, ColumnType};
+use crate::common::Rect;
+use crate::error::Result;
+use crate::pos::Pos;
+use crate::settings;
+
+const CHECK_STYLE_KEY: &str = "check_style";
+
+// how rows are rendered when their checkbox is checked, a table wide setting
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckStyle {
+    Strike,
+    Dim,
+}
+
+impl CheckStyle {
+    pub fn parse(s: &str) -> Option<CheckStyle> {
+        match s {
+            "strike" => Some(CheckStyle::Strike),
+            "dim" => Some(CheckStyle::Dim),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CheckStyle::Strike => "strike",
+            CheckStyle::Dim => "dim",
+        }
+    }
+}
+
+pub(crate) fn is_checked(data: &Data) -> bool {
+    parse_bool(&data.to_string()) == Some(true)
+}
+
+// [x] or [ ], cells that aren't booleans are shown as they are
+pub(crate) fn display(data: &Data) -> Option<String> {
+    match data {
+        Data::Empty => Some("[ ]".to_string()),
+        _ => parse_bool(&data.to_string()).map(|b| if b { "[x]" } else { "[ ]" }.to_string()),
+    }
+}
+
+// the bool column that decides whether a row is done: the preferred column if it is one,
+// otherwise the first bool column of the table
+pub(crate) fn bool_column(
+    column_types: &[Option<ColumnType>],
+    preferred_x: Option<usize>,
+) -> Option<usize> {
+    if let Some(x) = preferred_x {
+        if let Some(Some(ColumnType::Bool)) = column_types.get(x) {
+            return Some(x);
+        }
+    }
+    column_types
+        .iter()
+        .position(|column_type| *column_type == Some(ColumnType::Bool))
+}
+
+// toggle the checkboxes under the cursor or in the selection
+pub(crate) fn toggle(
+    db: &mut Db,
+    table_name: &str,
+    cursor: &Pos,
+    selection: &Option<Pos>,
+) -> Result<()> {
+    let r = match selection {
+        Some(anchor) => Rect::from_corners(anchor, cursor),
+        None => Rect::from_corners(cursor, cursor),
+    };
+    let column_types = column_type::get_all(db, table_name);
+    let row_count = db.get_row_count(table_name)?;
+    let mut toggled = false;
+    for x in r.start_x..r.end_x {
+        if column_types.get(x - 1) != Some(&Some(ColumnType::Bool)) {
+            continue;
+        }
+        for y in r.start_y.max(1)..r.end_y.min(row_count + 1) {
+            let checked = is_checked(&db.select_at(table_name, x - 1, y - 1)?);
+            let text = column_type::bool_to_string(!checked);
+            db.set_at(table_name, y - 1, x - 1, Data::String(text))?;
+            toggled = true;
+        }
+    }
+    if toggled {
+        Ok(())
+    } else {
+        Err("No checkbox here, declare a column with :settype bool".into())
+    }
+}
+
+pub(crate) fn get_style(db: &Db, table_name: &str) -> Option<CheckStyle> {
+    settings::get(db, table_name, "", CHECK_STYLE_KEY).and_then(|s| CheckStyle::parse(&s))
+}
+
+// None renders checked rows like all others
+pub(crate) fn set_style(db: &mut Db, table_name: &str, style: Option<CheckStyle>) -> Result<()> {
+    let value = style.map(|style| style.name()).unwrap_or_default();
+    settings::set(db, table_name, "", CHECK_STYLE_KEY, value)
+}
@@ -0,0 +1,237 @@
+use rzdb::{Data, Db};
+
+use crate::error::Result;
+use crate::settings;
+
+const TYPE_KEY: &str = "type";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    Int,
+    Float,
+    Date,
+    Time,
+    String,
+    Bool,
+    Enum(Vec<String>),
+}
+
+impl ColumnType {
+    // int, float, date, time, string, bool or enum:value1,value2,...
+    pub fn parse(s: &str) -> Option<ColumnType> {
+        let (name, values) = match s.split_once(':') {
+            Some((name, values)) => (name, Some(values)),
+            None => (s, None),
+        };
+        match (name.to_lowercase().as_str(), values) {
+            ("int", None) => Some(ColumnType::Int),
+            ("float", None) => Some(ColumnType::Float),
+            ("date", None) => Some(ColumnType::Date),
+            ("time", None) => Some(ColumnType::Time),
+            ("string", None) => Some(ColumnType::String),
+            ("bool", None) => Some(ColumnType::Bool),
+            ("enum", Some(values)) => {
+                let values: Vec<String> = values
+                    .split(',')
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+                    .collect();
+                // an enum without values would reject everything
+                (!values.is_empty()).then_some(ColumnType::Enum(values))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColumnType::Int => "int",
+            ColumnType::Float => "float",
+            ColumnType::Date => "date",
+            ColumnType::Time => "time",
+            ColumnType::String => "string",
+            ColumnType::Bool => "bool",
+            ColumnType::Enum(_) => "enum",
+        }
+    }
+
+    pub fn to_setting(&self) -> String {
+        match self {
+            ColumnType::Enum(values) => format!("enum:{}", values.join(",")),
+            _ => self.name().to_string(),
+        }
+    }
+
+    // convert text typed into a cell, or explain why it doesn't fit
+    pub fn convert(&self, text: &str) -> std::result::Result<Data, String> {
+        if text.trim().is_empty() {
+            return Ok(Data::Empty);
+        }
+        let data = Data::parse(text);
+        let converted = match self {
+            ColumnType::Int => match data {
+                Data::Int(_) => Some(data),
+                Data::Float(f) if f.fract() == 0.0 => Some(Data::parse(&format!("{}", f as i64))),
+                _ => None,
+            },
+            ColumnType::Float => match data {
+                Data::Float(_) => Some(data),
+                Data::Int(i) => Some(Data::Float(i as f64)),
+                _ => None,
+            },
+            ColumnType::Date => matches!(data, Data::Date(_)).then_some(data),
+            ColumnType::Time => matches!(data, Data::Time(_)).then_some(data),
+            ColumnType::String => Some(Data::String(text.to_string())),
+            ColumnType::Bool => parse_bool(text).map(|b| Data::String(bool_to_string(b))),
+            ColumnType::Enum(values) => values
+                .iter()
+                .find(|value| value.eq_ignore_ascii_case(text.trim()))
+                .map(|value| Data::String(value.clone())),
+        };
+        converted.ok_or_else(|| match self {
+            ColumnType::Enum(values) => format!("'{}' is not one of {}", text, values.join(", ")),
+            _ => format!("'{}' is not a valid {}", text, self.name()),
+        })
+    }
+
+    // whether a stored cell fits the declared type
+    pub fn conforms(&self, data: &Data) -> bool {
+        match (self, data) {
+            (_, Data::Empty) => true,
+            (ColumnType::Int, Data::Int(_)) => true,
+            (ColumnType::Float, Data::Int(_) | Data::Float(_)) => true,
+            (ColumnType::Date, Data::Date(_)) => true,
+            (ColumnType::Time, Data::Time(_)) => true,
+            (ColumnType::String, _) => true,
+            (ColumnType::Bool | ColumnType::Enum(_), Data::String(_)) => {
+                self.convert(&data.to_string()).is_ok()
+            }
+            _ => false,
+        }
+    }
+}
+
+// text written into a cell by a command, like an indented outline row: converted to the
+// declared type when it fits, otherwise stored as it is and shown as invalid
+pub(crate) fn parse_text(column_type: Option<&ColumnType>, text: &str) -> Data {
+    column_type
+        .and_then(|column_type| column_type.convert(text).ok())
+        .unwrap_or_else(|| Data::parse(text))
+}
+
+// cells written by paste, fill or an external editor are converted to the declared type
+// when they fit, otherwise they are kept as they are and shown as invalid
+pub(crate) fn coerce(column_type: Option<&ColumnType>, data: Data) -> Data {
+    match column_type {
+        Some(column_type) if !matches!(data, Data::Empty) => {
+            column_type.convert(&data.to_string()).unwrap_or(data)
+        }
+        _ => data,
+    }
+}
+
+pub(crate) fn parse_bool(text: &str) -> Option<bool> {
+    match text.trim().to_lowercase().as_str() {
+        "true" | "yes" | "y" | "x" | "1" | "[x]" => Some(true),
+        "false" | "no" | "n" | "0" | "[ ]" | "[]" => Some(false),
+        _ => None,
+    }
+}
+
+pub(crate) fn bool_to_string(b: bool) -> String {
+    if b { "true" } else { "false" }.to_string()
+}
+
+pub(crate) fn get(db: &Db, table_name: &str, column_name: &str) -> Option<ColumnType> {
+    settings::get(db, table_name, column_name, TYPE_KEY).and_then(|s| ColumnType::parse(&s))
+}
+
+// declared type of each column, in column order
+pub(crate) fn get_all(db: &Db, table_name: &str) -> Vec<Option<ColumnType>> {
+    settings::get_for_columns(db, table_name, TYPE_KEY)
+        .iter()
+        .map(|s| s.as_ref().and_then(|s| ColumnType::parse(s)))
+        .collect()
+}
+
+// declare the type of a column and convert its cells, returns the cells that didn't fit
+// as (row, text), None removes the declaration
+pub(crate) fn set(
+    db: &mut Db,
+    table_name: &str,
+    column_name: &str,
+    column_type: Option<ColumnType>,
+) -> Result<Vec<(usize, String)>> {
+    let x = match db
+        .get_column_names(table_name)?
+        .iter()
+        .position(|name| name == column_name)
+    {
+        Some(x) => x,
+        None => return Err(format!("Column {} does not exist", column_name).into()),
+    };
+    let setting = column_type
+        .as_ref()
+        .map(|column_type| column_type.to_setting())
+        .unwrap_or_default();
+    settings::set(db, table_name, column_name, TYPE_KEY, &setting)?;
+
+    let mut failures = vec![];
+    if let Some(column_type) = column_type {
+        for y in 0..db.get_row_count(table_name)? {
+            let text = db.select_at(table_name, x, y)?.to_string();
+            match column_type.convert(&text) {
+                Ok(data) => db.set_at(table_name, y, x, data)?,
+                Err(_) => failures.push((y + 1, text)),
+            }
+        }
+    }
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_column_type() {
+        assert_eq!(ColumnType::parse("Date"), Some(ColumnType::Date));
+        assert_eq!(ColumnType::parse("bool"), Some(ColumnType::Bool));
+        assert_eq!(
+            ColumnType::parse("enum:open, done,"),
+            Some(ColumnType::Enum(vec![
+                "open".to_string(),
+                "done".to_string()
+            ]))
+        );
+        assert_eq!(ColumnType::parse("enum"), None);
+        assert_eq!(ColumnType::parse("enum:"), None);
+        assert_eq!(ColumnType::parse("enum: ,"), None);
+        assert_eq!(ColumnType::parse("number"), None);
+        let column_type = ColumnType::parse("enum:a,b").unwrap();
+        assert_eq!(
+            ColumnType::parse(&column_type.to_setting()),
+            Some(column_type)
+        );
+    }
+
+    #[test]
+    fn test_coerce() {
+        let int = Some(&ColumnType::Int);
+        assert!(matches!(coerce(int, Data::parse("3.0")), Data::Int(_)));
+        assert!(matches!(coerce(int, Data::parse("x")), Data::String(_)));
+        let bool = Some(&ColumnType::Bool);
+        assert_eq!(coerce(bool, Data::parse("yes")).to_string(), "true");
+        assert!(matches!(coerce(None, Data::parse("3")), Data::Int(_)));
+        // an indented outline row in a string column stays text
+        let string = Some(&ColumnType::String);
+        assert!(matches!(parse_text(string, "  5"), Data::String(_)));
+    }
+
+    #[test]
+    fn test_parse_bool() {
+        assert_eq!(parse_bool(" Yes"), Some(true));
+        assert_eq!(parse_bool("[ ]"), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+    }
+}
@@ -1,8 +1,12 @@
+use crate::fill::FillMode;
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum Command {
     Quit,
     None,
     PreviousFile,
+    GotoReference,
 
     EditorExit,
     EditorExitLeft,
@@ -10,6 +14,8 @@ pub enum Command {
     EditorExitUp,
     EditorExitDown,
     EditorNewLine,
+    EditorEnter,
+    EditorShiftEnter,
 
     CommandLineEnter,
     CommandLineExit,
@@ -20,28 +26,118 @@ pub enum Command {
     InsertStart,
     InsertEnd,
     ChangeCell,
-    DeleteCell,
+    Calendar,
+    ExternalEdit,
 
     PasteToday,
+    PasteNow,
+    Increment(isize),
+    ToggleCheckbox,
+
+    ToggleFold,
+    FoldAll,
+    UnfoldAll,
+    MoveSubtreeUp,
+    MoveSubtreeDown,
+    IndentSubtreeLeft,
+    IndentSubtreeRight,
 
     InsertEmptyColumn,
     InsertEmptyRowAbove,
     InsertEmptyRowBelow,
-    DeleteLine,
-    DeleteColumn,
-    IndentLeft,
-    IndentRight,
 
-    YankCell,
-    YankRow,
-    YankColumn,
+    // operator, motion, count
+    Operate(Operator, Motion, usize),
+    // move rows or columns in the direction of the motion, count steps
+    Move(Motion, usize),
+    Fill(FillMode),
+
     PasteReplace,
     PasteBefore,
     PasteAfter,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Operator {
+    Delete,
+    Yank,
+    Change,
+    IndentLeft,
+    IndentRight,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordForward,
+    WordBackward,
+    ParagraphForward,
+    ParagraphBackward,
+    FirstRow,
+    LastRow,
+    Row(usize),
+    // whole rows/columns starting at the cursor, as in dd or dc
+    Line,
+    Column,
+}
+
 impl Command {
     pub fn new() -> Command {
         Command::None
     }
+
+    // commands that . repeats
+    pub fn is_repeatable(&self) -> bool {
+        matches!(
+            self,
+            Command::PasteToday
+                | Command::PasteNow
+                | Command::Increment(_)
+                | Command::ToggleCheckbox
+                | Command::MoveSubtreeUp
+                | Command::MoveSubtreeDown
+                | Command::IndentSubtreeLeft
+                | Command::IndentSubtreeRight
+                | Command::InsertEmptyColumn
+                | Command::InsertEmptyRowAbove
+                | Command::InsertEmptyRowBelow
+                | Command::Operate(_, _, _)
+                | Command::Move(_, _)
+                | Command::Fill(_)
+                | Command::PasteReplace
+                | Command::PasteBefore
+                | Command::PasteAfter
+        )
+    }
+}
+
+impl Operator {
+    pub fn from_key(ch: char) -> Option<Operator> {
+        match ch {
+            'd' => Some(Operator::Delete),
+            'y' => Some(Operator::Yank),
+            'c' => Some(Operator::Change),
+            _ => None,
+        }
+    }
+}
+
+impl Motion {
+    // motions that cover whole rows when used with an operator
+    pub fn is_linewise(&self) -> bool {
+        matches!(
+            self,
+            Motion::Up
+                | Motion::Down
+                | Motion::ParagraphForward
+                | Motion::ParagraphBackward
+                | Motion::FirstRow
+                | Motion::LastRow
+                | Motion::Row(_)
+                | Motion::Line
+        )
+    }
 }
@@ -1,10 +1,30 @@
-use crate::command::Command;
+use crate::aggregate::{self, Aggregate};
+use crate::calendar;
+use crate::checkbox::{self, CheckStyle};
+use crate::column_type::{self, ColumnType};
+use crate::command::{Command, Motion, Operator};
+use crate::date::{self, Hms, Ymd};
 use crate::editor;
+use crate::enter::{self, EnterMode};
+use crate::error::{Error, Result};
+use crate::fill::{self, FillMode};
+use crate::format;
+use crate::highlight;
+use crate::join::{self, Join};
 use crate::mode::Mode;
+use crate::motion::{self, RangeKind};
+use crate::notes;
+use crate::outline;
 use crate::pos::{self, Pos};
+use crate::registers;
+use crate::rowid;
+use crate::settings;
+use crate::sort;
+use crate::tsv;
 use crate::State;
-use rzdb::{Data, Db};
+use rzdb::{time::Date, Data, Db};
 
+#[derive(Clone, Copy)]
 pub struct Rect {
     pub start_x: usize,
     pub start_y: usize,
@@ -12,29 +32,45 @@ pub struct Rect {
     pub end_y: usize,
 }
 
+impl Rect {
+    // rectangle spanned by two corner cells, both included
+    pub fn from_corners(a: &Pos, b: &Pos) -> Rect {
+        Rect {
+            start_x: a.x.min(b.x),
+            start_y: a.y.min(b.y),
+            end_x: a.x.max(b.x) + 1,
+            end_y: a.y.max(b.y) + 1,
+        }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.start_x && x < self.end_x && y >= self.start_y && y < self.end_y
+    }
+}
+
 pub(crate) fn is_cell(db: &Db, state: &State, x: usize, y: usize) -> bool {
-    x < db.get_column_count(&state.table_name).unwrap()
-        && y < db.get_row_count(&state.table_name).unwrap()
+    x < db.get_column_count(&state.table_name).unwrap_or(0)
+        && y < db.get_row_count(&state.table_name).unwrap_or(0)
 }
 
 pub(crate) fn get_column_names_extended(db: &Db, table_name: &str, x: usize) -> Vec<String> {
-    let mut names = db.get_column_names(table_name).unwrap();
+    let mut names = db.get_column_names(table_name).unwrap_or_default();
     for idx in names.len()..=x {
         names.push(generate_column_name(db, table_name, idx + 1));
     }
     names
 }
 
-pub(crate) fn get_column_name_or_generic(x: usize, db: &Db, table_name: &str) -> String {
-    if x <= db.get_column_count(table_name).unwrap() {
-        db.get_column_name_at(table_name, x - 1).unwrap()
+pub(crate) fn get_column_name_or_generic(x: usize, db: &Db, table_name: &str) -> Result<String> {
+    if x <= db.get_column_count(table_name)? {
+        Ok(db.get_column_name_at(table_name, x - 1)?)
     } else {
-        generate_column_name(db, table_name, x - 1)
+        Ok(generate_column_name(db, table_name, x - 1))
     }
 }
 
 pub(crate) fn generate_column_name(db: &Db, table_name: &str, x: usize) -> String {
-    let column_names = db.get_column_names(table_name).unwrap();
+    let column_names = db.get_column_names(table_name).unwrap_or_default();
     let mut x = x;
     loop {
         let new_name = format!("Column {}", x);
@@ -45,6 +81,16 @@ pub(crate) fn generate_column_name(db: &Db, table_name: &str, x: usize) -> Strin
     }
 }
 
+// tables rspread keeps for itself, not shown by :ls
+pub(crate) fn is_hidden_table(table_name: &str) -> bool {
+    table_name == "."
+        || table_name == settings::SETTINGS_TABLE
+        || table_name == notes::NOTES_TABLE
+        || table_name == rowid::ROW_IDS_TABLE
+        || table_name == highlight::HIGHLIGHTS_TABLE
+        || table_name.starts_with(registers::REGISTER_PREFIX)
+}
+
 pub(crate) fn set_error_message(new_message: &str, message: &mut String, mode: &mut Mode) {
     *message = new_message.to_string();
     *mode = Mode::Error;
@@ -90,17 +136,18 @@ pub(crate) fn load_table(
     cursor: &mut pos::Pos,
     db: &mut Db,
     editor: &mut editor::Editor,
-) {
+) -> Result<()> {
     if let Some(arg1) = args.next() {
         // set table_name to new arg
         let new_table_name = arg1.to_string();
         set_table(&new_table_name, state, previous_table_name, cursor);
         if !db.exists(&state.table_name) {
-            db.create_table(&state.table_name).unwrap();
+            db.create_table(&state.table_name)?;
         }
         editor.clear();
         *cursor = pos::Pos::new(1, 1);
     }
+    Ok(())
 }
 
 pub(crate) fn drop_table(
@@ -110,16 +157,19 @@ pub(crate) fn drop_table(
     previous_table_name: &mut String,
     cursor: &mut pos::Pos,
     mode: &mut Mode,
-) -> Result<(), String> {
+) -> Result<()> {
     if let Some(arg1) = args.next() {
         let name = arg1.to_string();
         if db.drop_table(&name).is_err() {
-            return Err(format!("Table {} does not exist", name));
+            return Err(format!("Table {} does not exist", name).into());
         }
-        list_tables(state, previous_table_name, cursor, db, mode);
-        Ok(())
+        rowid::drop_table(db, &name)?;
+        settings::remove_table(db, &name)?;
+        notes::remove_table(db, &name)?;
+        highlight::remove_table(db, &name)?;
+        list_tables(state, previous_table_name, cursor, db, mode)
     } else {
-        Err("No table name given".to_string())
+        Err("No table name given".into())
     }
 }
 
@@ -129,19 +179,20 @@ pub(crate) fn list_tables(
     cursor: &mut pos::Pos,
     db: &mut Db,
     mode: &mut Mode,
-) {
+) -> Result<()> {
     let new_table_name = ".".to_string();
     set_table(&new_table_name, state, previous_table_name, cursor);
-    db.create_or_replace_table(&state.table_name).unwrap();
-    db.create_column(&state.table_name, "name").unwrap();
+    db.create_or_replace_table(&state.table_name)?;
+    db.create_column(&state.table_name, "name")?;
     let mut table_names = db.get_table_names();
     table_names.sort();
     for table in table_names {
-        if &table != "." {
-            db.insert(&state.table_name, vec![&table]).unwrap();
+        if !is_hidden_table(&table) {
+            db.insert(&state.table_name, vec![&table])?;
         }
     }
     *mode = Mode::ListTables;
+    Ok(())
 }
 
 pub(crate) fn list_databases(
@@ -150,17 +201,544 @@ pub(crate) fn list_databases(
     cursor: &mut pos::Pos,
     db: &mut Db,
     mode: &mut Mode,
-) {
+) -> Result<()> {
     let new_table_name = ".".to_string();
     set_table(&new_table_name, state, previous_table_name, cursor);
-    db.create_or_replace_table(&state.table_name).unwrap();
-    db.create_column(&state.table_name, "name").unwrap();
-    let mut database_names = db.get_database_names().unwrap();
+    db.create_or_replace_table(&state.table_name)?;
+    db.create_column(&state.table_name, "name")?;
+    let mut database_names = db.get_database_names()?;
     database_names.sort();
     for database in database_names {
-        db.insert(&state.table_name, vec![&database]).unwrap();
+        db.insert(&state.table_name, vec![&database])?;
     }
     *mode = Mode::ListDatabases;
+    Ok(())
+}
+
+pub(crate) fn print_working_directory(
+    state: &mut State,
+    previous_table_name: &mut String,
+    cursor: &mut pos::Pos,
+    db: &mut Db,
+    mode: &mut Mode,
+) -> Result<()> {
+    let new_table_name = ".".to_string();
+    set_table(&new_table_name, state, previous_table_name, cursor);
+    db.create_or_replace_table(&state.table_name)?;
+    db.create_column(&state.table_name, "name")?;
+    db.create_column(&state.table_name, "value")?;
+    db.insert(&state.table_name, vec!["database path", &state.db_dir])?;
+    db.insert(&state.table_name, vec!["database name", &state.db_name])?;
+    *mode = Mode::ListReadOnly;
+    Ok(())
+}
+
+pub(crate) fn show_registers(
+    state: &mut State,
+    previous_table_name: &mut String,
+    cursor: &mut pos::Pos,
+    db: &mut Db,
+    mode: &mut Mode,
+    clipboard_table_name: &str,
+) -> Result<()> {
+    let new_table_name = ".".to_string();
+    set_table(&new_table_name, state, previous_table_name, cursor);
+    registers::list_registers(db, &state.table_name, clipboard_table_name)?;
+    *mode = Mode::ListReadOnly;
+    Ok(())
+}
+
+pub(crate) fn set_column_type(
+    args: std::str::SplitWhitespace,
+    db: &mut Db,
+    state: &State,
+    cursor: &pos::Pos,
+) -> Result<()> {
+    let mut args: Vec<&str> = args.collect();
+    let type_name = match args.pop() {
+        Some(type_name) => type_name,
+        None => return Err("usage: settype [<column>] <type>|none".into()),
+    };
+    let column_name = if args.is_empty() {
+        get_column_name_or_generic(cursor.x, db, &state.table_name)?
+    } else {
+        args.join(" ")
+    };
+    let column_type = match type_name {
+        "none" => None,
+        _ => Some(ColumnType::parse(type_name).ok_or_else(|| {
+            Error::Message(format!(
+                "Unknown type {}, use int, float, date, time, string, bool or enum:a,b,c",
+                type_name
+            ))
+        })?),
+    };
+    let failures = column_type::set(db, &state.table_name, &column_name, column_type)?;
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        let list = failures
+            .iter()
+            .map(|(y, text)| format!("row {} '{}'", y, text))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Err(format!(
+            "{} cells of {} could not be converted: {}",
+            failures.len(),
+            column_name,
+            list
+        )
+        .into())
+    }
+}
+
+// display format of the cursor column, e. g. %.2f, 1,234.50 €, %d.%m.%Y, %H:%M or relative,
+// or a conditional format with :format if [<column>] <operator> <value> <style>
+pub(crate) fn set_column_format(
+    args: std::str::SplitWhitespace,
+    db: &mut Db,
+    state: &State,
+    cursor: &pos::Pos,
+) -> Result<()> {
+    let words = args.collect::<Vec<_>>();
+    if words.first() == Some(&"if") {
+        return add_highlight(&words[1..], db, state, cursor);
+    }
+    let spec = words.join(" ");
+    if spec.is_empty() {
+        return Err(
+            "usage: format <format>|none or format if [<column>] <op> <value> <style>".into(),
+        );
+    }
+    let column_name = get_column_name_or_generic(cursor.x, db, &state.table_name)?;
+    format::set(db, &state.table_name, &column_name, &spec)
+}
+
+// e. g. :format if date < today fg=red row, or :format if = urgent bold for the cursor column.
+// :format if none removes the rules of the cursor column.
+fn add_highlight(words: &[&str], db: &mut Db, state: &State, cursor: &pos::Pos) -> Result<()> {
+    if words == ["none"] {
+        let column_name = get_column_name_or_generic(cursor.x, db, &state.table_name)?;
+        return highlight::clear(db, &state.table_name, &column_name);
+    }
+    let (column_name, words) = match words.first() {
+        Some(word) if highlight::Operator::parse(word).is_none() => {
+            if !db
+                .get_column_names(&state.table_name)?
+                .iter()
+                .any(|name| name == word)
+            {
+                return Err(format!("No column {} in this table", word).into());
+            }
+            (word.to_string(), &words[1..])
+        }
+        _ => (
+            get_column_name_or_generic(cursor.x, db, &state.table_name)?,
+            words,
+        ),
+    };
+    match words {
+        [operator, value, style @ ..] if !style.is_empty() => highlight::add(
+            db,
+            &state.table_name,
+            &column_name,
+            operator,
+            value,
+            &style.join(" "),
+        ),
+        _ => Err("usage: format if [<column>] <op> <value> <style>|none".into()),
+    }
+}
+
+// footer aggregate of the cursor column
+pub(crate) fn set_column_aggregate(
+    args: std::str::SplitWhitespace,
+    db: &mut Db,
+    state: &State,
+    cursor: &pos::Pos,
+) -> Result<()> {
+    let name = args.collect::<Vec<_>>().join(" ");
+    let aggregate = match name.as_str() {
+        "" => return Err("usage: aggregate sum|avg|min|max|count|count empty|none".into()),
+        "none" => None,
+        _ => Some(Aggregate::parse(&name).ok_or_else(|| {
+            Error::Message(format!(
+                "Unknown aggregate {}, use sum, avg, min, max, count or count empty",
+                name
+            ))
+        })?),
+    };
+    let column_name = get_column_name_or_generic(cursor.x, db, &state.table_name)?;
+    aggregate::set(db, &state.table_name, &column_name, aggregate)
+}
+
+// make the cursor column refer to the key column of another table
+pub(crate) fn set_column_join(
+    args: std::str::SplitWhitespace,
+    db: &mut Db,
+    state: &State,
+    cursor: &pos::Pos,
+) -> Result<()> {
+    let args: Vec<&str> = args.collect();
+    let join = match args.as_slice() {
+        ["none"] => None,
+        [table_name, key_column] | [table_name, key_column, _] => Some(Join {
+            table_name: table_name.to_string(),
+            key_column: key_column.to_string(),
+            display_column: args.get(2).unwrap_or(key_column).to_string(),
+        }),
+        _ => return Err("usage: join <table> <key column> [<display column>]|none".into()),
+    };
+    let column_name = get_column_name_or_generic(cursor.x, db, &state.table_name)?;
+    join::set(db, &state.table_name, &column_name, join)
+}
+
+// gd: jump to the row a joined cell refers to
+pub(crate) fn goto_reference(
+    db: &Db,
+    state: &mut State,
+    previous_table_name: &mut String,
+    cursor: &mut pos::Pos,
+) -> Result<()> {
+    if cursor.y == 0 || !is_cell(db, state, cursor.x - 1, cursor.y - 1) {
+        return Err("No cell under the cursor".into());
+    }
+    let column_name = db.get_column_name_at(&state.table_name, cursor.x - 1)?;
+    let join = join::get(db, &state.table_name, &column_name)
+        .ok_or_else(|| Error::Message(format!("Column {} is not a join", column_name)))?;
+    let key = db
+        .select_at(&state.table_name, cursor.x - 1, cursor.y - 1)?
+        .to_string();
+    let y = join.find_row(db, &key).ok_or_else(|| {
+        Error::Message(format!(
+            "'{}' is not a key of {}:{}",
+            key, join.table_name, join.key_column
+        ))
+    })?;
+    let x = join.key_x(db)?;
+    set_table(&join.table_name, state, previous_table_name, cursor);
+    *cursor = Pos::new(x + 1, y + 1);
+    Ok(())
+}
+
+// fill down: copy, series, weekdays or monthly
+pub(crate) fn fill_cells(
+    mut args: std::str::SplitWhitespace,
+    db: &mut Db,
+    state: &State,
+    cursor: &pos::Pos,
+    selection: &Option<pos::Pos>,
+) -> Result<()> {
+    let fill_mode = match args.next() {
+        None => FillMode::Copy,
+        Some(name) => FillMode::parse(name).ok_or_else(|| {
+            Error::Message(format!(
+                "Unknown fill mode {}, use copy, series, weekdays or monthly",
+                name
+            ))
+        })?,
+    };
+    fill::fill(db, state, cursor, selection, fill_mode)
+}
+
+// sort the rows by the cursor column
+pub(crate) fn sort_by_column(
+    mut args: std::str::SplitWhitespace,
+    db: &mut Db,
+    state: &State,
+    cursor: &pos::Pos,
+) -> Result<()> {
+    let descending = match args.next() {
+        None | Some("asc") => false,
+        Some("desc") => true,
+        Some(_) => return Err("usage: sort [asc|desc]".into()),
+    };
+    if cursor.x > db.get_column_count(&state.table_name)? {
+        return Err("No column under the cursor".into());
+    }
+    sort::sort_rows(db, &state.table_name, cursor.x - 1, descending)
+}
+
+// list the rows that are done or still open in a read only view
+pub(crate) fn filter_by_checkbox(
+    mut args: std::str::SplitWhitespace,
+    state: &mut State,
+    previous_table_name: &mut String,
+    cursor: &mut pos::Pos,
+    db: &mut Db,
+    mode: &mut Mode,
+) -> Result<()> {
+    let done = match args.next() {
+        Some("done") => true,
+        Some("open") => false,
+        _ => return Err("usage: filter done|open".into()),
+    };
+    let column_types = column_type::get_all(db, &state.table_name);
+    let x = checkbox::bool_column(&column_types, Some(cursor.x - 1))
+        .ok_or_else(|| Error::Message("No bool column, declare one with :settype bool".into()))?;
+    let column_names = db.get_column_names(&state.table_name)?;
+    let rows = db.select_from(&state.table_name)?;
+
+    let list_table_name = ".";
+    db.create_or_replace_table(list_table_name)?;
+    db.create_column(list_table_name, "row")?;
+    for column_name in &column_names {
+        db.create_column(list_table_name, column_name)?;
+    }
+    for (y, row) in rows.iter().enumerate() {
+        if checkbox::is_checked(&row.select_at(x).unwrap_or(Data::Empty)) != done {
+            continue;
+        }
+        let mut texts = vec![(y + 1).to_string()];
+        texts.extend((0..column_names.len()).map(|x| {
+            row.select_at(x)
+                .map(|data| data.to_string())
+                .unwrap_or_default()
+        }));
+        db.insert(list_table_name, texts.iter().map(|s| s.as_str()).collect())?;
+    }
+    set_table(list_table_name, state, previous_table_name, cursor);
+    *mode = Mode::ListReadOnly;
+    Ok(())
+}
+
+// the cursor column becomes the outline column, "none" turns the outline off
+pub(crate) fn set_outline_column(
+    mut args: std::str::SplitWhitespace,
+    db: &mut Db,
+    state: &State,
+    cursor: &pos::Pos,
+) -> Result<()> {
+    match args.next() {
+        Some("none") => outline::set_column(db, &state.table_name, None),
+        None => {
+            let column_name = get_column_name_or_generic(cursor.x, db, &state.table_name)?;
+            outline::set_column(db, &state.table_name, Some(&column_name))
+        }
+        Some(_) => Err("usage: outline [none]".into()),
+    }
+}
+
+// strike, dim or none
+pub(crate) fn set_check_style(
+    mut args: std::str::SplitWhitespace,
+    db: &mut Db,
+    state: &State,
+) -> Result<()> {
+    let style = match args.next() {
+        Some("none") => None,
+        Some(name) => Some(CheckStyle::parse(name).ok_or_else(|| {
+            Error::Message(format!("Unknown style {}, use strike, dim or none", name))
+        })?),
+        None => return Err("usage: checkstyle strike|dim|none".into()),
+    };
+    checkbox::set_style(db, &state.table_name, style)
+}
+
+// :note <text> comments the cell under the cursor, :note alone removes the comment
+pub(crate) fn set_note(
+    args: std::str::SplitWhitespace,
+    db: &mut Db,
+    state: &State,
+    cursor: &pos::Pos,
+) -> Result<()> {
+    if cursor.y == 0 {
+        return Err("Notes belong to cells, not to column names".into());
+    }
+    let text = args.collect::<Vec<_>>().join(" ");
+    if !text.is_empty() {
+        extend_table(db, &state.table_name, cursor.x, cursor.y)?;
+    }
+    let column_name = get_column_name_or_generic(cursor.x, db, &state.table_name)?;
+    let row_id = rowid::get(db, &state.table_name, cursor.y)?;
+    notes::set(db, &state.table_name, row_id, &column_name, &text)
+}
+
+// the id of the row under the cursor, it stays the same when rows move around
+pub(crate) fn show_row_id(db: &mut Db, state: &State, cursor: &pos::Pos) -> Result<String> {
+    if cursor.y == 0 {
+        return Err("The column header has no row id".into());
+    }
+    let row_id = rowid::get(db, &state.table_name, cursor.y)?;
+    Ok(format!("Row {} has id {}", cursor.y, row_id))
+}
+
+pub(crate) fn set_enter_mode(
+    mut args: std::str::SplitWhitespace,
+    db: &mut Db,
+    state: &State,
+) -> Result<()> {
+    let enter_mode = match args.next() {
+        Some("default") => None,
+        Some(name) => Some(EnterMode::parse(name).ok_or_else(|| {
+            Error::Message(format!(
+                "Unknown Enter mode {}, use down, newrow or default",
+                name
+            ))
+        })?),
+        None => return Err("usage: enter down|newrow|default".into()),
+    };
+    enter::set(db, &state.table_name, enter_mode)
+}
+
+// rows [start, mid) and [mid, end) trade places, 0-indexed
+pub(crate) fn rotate_rows(
+    db: &mut Db,
+    table_name: &str,
+    start: usize,
+    mid: usize,
+    end: usize,
+) -> Result<()> {
+    let column_count = db.get_column_count(table_name)?;
+    let mut rows = vec![];
+    for y in start..end {
+        let mut row = vec![];
+        for x in 0..column_count {
+            row.push(db.select_at(table_name, x, y)?);
+        }
+        rows.push(row);
+    }
+    rows.rotate_left(mid - start);
+    for (y, row) in rows.into_iter().enumerate() {
+        for (x, data) in row.into_iter().enumerate() {
+            db.set_at(table_name, start + y, x, data)?;
+        }
+    }
+    rowid::rotate(db, table_name, start, mid, end)
+}
+
+// the settings, notes and formatting rules of a column follow it to its new name
+pub(crate) fn rename_column(
+    db: &mut Db,
+    table_name: &str,
+    old_column_name: &str,
+    new_column_name: &str,
+) -> Result<()> {
+    db.rename_column(table_name, old_column_name, new_column_name)?;
+    settings::rename_column(db, table_name, old_column_name, new_column_name)?;
+    notes::rename_column(db, table_name, old_column_name, new_column_name)?;
+    highlight::rename_column(db, table_name, old_column_name, new_column_name)
+}
+
+// columns [start, mid) and [mid, end) trade places, 0-indexed.
+// the names move along with the cells, so the column settings stay with their column.
+pub(crate) fn rotate_columns(
+    db: &mut Db,
+    table_name: &str,
+    start: usize,
+    mid: usize,
+    end: usize,
+) -> Result<()> {
+    let mut names = db.get_column_names(table_name)?[start..end].to_vec();
+    // rename via temporary names, so that no two columns share a name on the way
+    for (idx, name) in names.iter().enumerate() {
+        db.rename_column(table_name, name, &format!(".move.{}", idx))?;
+    }
+    names.rotate_left(mid - start);
+    for (idx, name) in names.iter().enumerate() {
+        db.rename_column(table_name, &format!(".move.{}", idx), name)?;
+    }
+    for y in 0..db.get_row_count(table_name)? {
+        let mut cells = vec![];
+        for x in start..end {
+            cells.push(db.select_at(table_name, x, y)?);
+        }
+        cells.rotate_left(mid - start);
+        for (idx, data) in cells.into_iter().enumerate() {
+            db.set_at(table_name, y, start + idx, data)?;
+        }
+    }
+    Ok(())
+}
+
+// Alt-hjkl: move the row/column under the cursor, or the selected ones, count steps
+pub(crate) fn move_cells(
+    db: &mut Db,
+    state: &State,
+    cursor: &mut pos::Pos,
+    selection: &mut Option<pos::Pos>,
+    motion: Motion,
+    count: usize,
+) -> Result<()> {
+    let r = match selection {
+        Some(anchor) => Rect::from_corners(anchor, cursor),
+        None => Rect::from_corners(cursor, cursor),
+    };
+    let count = count.max(1);
+    let (length, start, end) = match motion {
+        Motion::Up | Motion::Down => {
+            if r.start_y == 0 {
+                return Err("The column header can't be moved, use Alt-h/l".into());
+            }
+            (db.get_row_count(&state.table_name)?, r.start_y, r.end_y)
+        }
+        _ => (db.get_column_count(&state.table_name)?, r.start_x, r.end_x),
+    };
+    if end - 1 > length {
+        return Err("Nothing to move here".into());
+    }
+    // 1-indexed, clamped to the table
+    let (block_start, mid, block_end, shift) = match motion {
+        Motion::Up | Motion::Left => {
+            let count = count.min(start - 1);
+            (start - count, start, end, -(count as isize))
+        }
+        _ => {
+            let count = count.min(length + 1 - end);
+            (start, end, end + count, count as isize)
+        }
+    };
+    if shift == 0 {
+        return Err("Can't move any further".into());
+    }
+    match motion {
+        Motion::Up | Motion::Down => rotate_rows(
+            db,
+            &state.table_name,
+            block_start - 1,
+            mid - 1,
+            block_end - 1,
+        )?,
+        _ => rotate_columns(
+            db,
+            &state.table_name,
+            block_start - 1,
+            mid - 1,
+            block_end - 1,
+        )?,
+    }
+    // cursor and selection move along
+    let shift_pos = |pos: &mut pos::Pos| match motion {
+        Motion::Up | Motion::Down => pos.y = (pos.y as isize + shift) as usize,
+        _ => pos.x = (pos.x as isize + shift) as usize,
+    };
+    shift_pos(cursor);
+    if let Some(anchor) = selection {
+        shift_pos(anchor);
+    }
+    Ok(())
+}
+
+// convert the existing cells of r to the declared column types
+pub(crate) fn coerce_cells(db: &mut Db, table_name: &str, r: &Rect) -> Result<()> {
+    let column_types = column_type::get_all(db, table_name);
+    let row_count = db.get_row_count(table_name)?;
+    for x in r.start_x.max(1)..r.end_x.min(column_types.len() + 1) {
+        let column_type = column_types[x - 1].as_ref();
+        if column_type.is_none() {
+            continue;
+        }
+        for y in r.start_y.max(1)..r.end_y.min(row_count + 1) {
+            let data = db.select_at(table_name, x - 1, y - 1)?;
+            db.set_at(
+                table_name,
+                y - 1,
+                x - 1,
+                column_type::coerce(column_type, data),
+            )?;
+        }
+    }
+    Ok(())
 }
 
 pub(crate) fn extend_table(
@@ -168,16 +746,15 @@ pub(crate) fn extend_table(
     table_name: &str,
     new_column_count: usize,
     new_row_count: usize,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let old_row_count = db.get_row_count(table_name).unwrap();
-    let old_column_count = db.get_column_count(table_name).unwrap();
+) -> Result<()> {
+    let old_row_count = db.get_row_count(table_name)?;
+    let old_column_count = db.get_column_count(table_name)?;
     for idx in old_column_count..new_column_count {
-        db.create_column(table_name, &generate_column_name(db, table_name, idx + 1))
-            .unwrap();
+        db.create_column(table_name, &generate_column_name(db, table_name, idx + 1))?;
     }
     for _ in old_row_count..new_row_count {
         let column_count = new_column_count.max(old_column_count);
-        db.insert(table_name, vec![""; column_count]).unwrap();
+        db.insert(table_name, vec![""; column_count])?;
     }
     Ok(())
 }
@@ -188,18 +765,16 @@ pub(crate) fn editor_enter(
     cursor: &pos::Pos,
     editor: &mut editor::Editor,
     cursor_x: i32,
-) {
-    let column_count = db.get_column_count(&state.table_name).unwrap();
+) -> Result<()> {
+    let column_count = db.get_column_count(&state.table_name)?;
     let old_text = if cursor.y == 0 {
         if cursor.x > column_count {
             generate_column_name(db, &state.table_name, cursor.x)
         } else {
-            db.get_column_name_at(&state.table_name, cursor.x - 1)
-                .unwrap()
+            db.get_column_name_at(&state.table_name, cursor.x - 1)?
         }
     } else if is_cell(db, state, cursor.x - 1, cursor.y - 1) {
-        db.select_at(&state.table_name, cursor.x - 1, cursor.y - 1)
-            .unwrap()
+        db.select_at(&state.table_name, cursor.x - 1, cursor.y - 1)?
             .no_time_seconds()
     } else {
         "".to_string()
@@ -214,6 +789,7 @@ pub(crate) fn editor_enter(
     } else {
         editor.insert_at(&old_text, cursor_x as usize);
     }
+    Ok(())
 }
 
 pub(crate) fn editor_exit(
@@ -222,29 +798,455 @@ pub(crate) fn editor_exit(
     mode: &mut Mode,
     cursor: &mut pos::Pos,
     editor: &mut editor::Editor,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<()> {
     if !editor.get_line().is_empty() {
         extend_table(db, &state.table_name, cursor.x, cursor.y)?;
     }
+    // the editor keeps the text if it is rejected
     let new_line = editor.get_line();
-    editor.clear(); // make sure to clear editor even if we quit with an error
     if cursor.y == 0 {
         // column name
-        let old_column_name = get_column_name_or_generic(cursor.x, db, &state.table_name);
+        let old_column_name = get_column_name_or_generic(cursor.x, db, &state.table_name)?;
         let new_column_name = new_line;
         if old_column_name != new_column_name {
-            db.rename_column(&state.table_name, &old_column_name, &new_column_name)?;
+            rename_column(db, &state.table_name, &old_column_name, &new_column_name)?;
         }
     } else if is_cell(db, state, cursor.x - 1, cursor.y - 1) {
+        let column_name = db.get_column_name_at(&state.table_name, cursor.x - 1)?;
+        let column_type = column_type::get(db, &state.table_name, &column_name);
+        let old_data = db.select_at(&state.table_name, cursor.x - 1, cursor.y - 1)?;
+        let new_line = expand_date_input(column_type.as_ref(), &old_data, &new_line);
+        let data = match column_type {
+            Some(column_type) => column_type
+                .convert(&new_line)
+                .map_err(|e| Error::Message(format!("{} in column {}", e, column_name)))?,
+            None => Data::parse(&new_line),
+        };
+        if let Some(join) = join::get(db, &state.table_name, &column_name) {
+            if !new_line.is_empty() && join.find_row(db, &new_line).is_none() {
+                return Err(format!(
+                    "'{}' is not a key of {}:{} in column {}",
+                    new_line, join.table_name, join.key_column, column_name
+                )
+                .into());
+            }
+        }
+        db.set_at(&state.table_name, cursor.y - 1, cursor.x - 1, data)?;
+    }
+    editor.clear();
+    *mode = Mode::Normal;
+    Ok(())
+}
+
+// today, +3d, next fri or now become the date or time they stand for in date and time
+// columns, and in untyped cells that already hold a date or time. other text stays as typed.
+fn expand_date_input(column_type: Option<&ColumnType>, old_data: &Data, text: &str) -> String {
+    let (takes_date, takes_time) = match column_type {
+        None => (
+            matches!(old_data, Data::Date(_)),
+            matches!(old_data, Data::Time(_)),
+        ),
+        Some(ColumnType::Date) => (true, false),
+        Some(ColumnType::Time) => (false, true),
+        Some(_) => (false, false),
+    };
+    if takes_time && text.trim().eq_ignore_ascii_case("now") {
+        return Hms::now().to_data().to_string();
+    }
+    if takes_date {
+        if let Some(date) = date::parse_expression(text, Ymd::today()) {
+            return date.to_data().to_string();
+        }
+    }
+    text.to_string()
+}
+
+// leave the editor, then move the cursor or open a new row depending on the command
+pub(crate) fn editor_exit_and_move(
+    db: &mut Db,
+    state: &State,
+    mode: &mut Mode,
+    cursor: &mut pos::Pos,
+    editor: &mut editor::Editor,
+    command: &Command,
+) -> Result<()> {
+    *mode = Mode::Normal;
+    if let Err(e) = editor_exit(db, state, mode, cursor, editor) {
+        // stay in the editor, so that the rejected text can be fixed
+        *mode = Mode::Insert;
+        return Err(e);
+    }
+    if *command == Command::EditorExit {
+        return Ok(());
+    }
+    let command = &match command {
+        Command::EditorEnter | Command::EditorShiftEnter => {
+            let new_row = enter::get(db, &state.table_name) == EnterMode::NewRow;
+            if new_row != (*command == Command::EditorShiftEnter) {
+                Command::EditorNewLine
+            } else {
+                Command::EditorExitDown
+            }
+        }
+        command => *command,
+    };
+    // Tab and Shift-Tab wrap around to the next/previous row
+    let column_count = db.get_column_count(&state.table_name)?;
+    if *command == Command::EditorExitLeft && cursor.x == 1 && cursor.y > 1 {
+        cursor.x = column_count.max(1);
+        cursor.y -= 1;
+    } else if *command == Command::EditorExitLeft && cursor.x > 1 {
+        cursor.x -= 1;
+    } else if *command == Command::EditorExitRight && cursor.x >= column_count && cursor.y > 0 {
+        cursor.x = 1;
+        cursor.y += 1;
+    } else if *command == Command::EditorExitRight {
+        cursor.x += 1;
+    } else if *command == Command::EditorExitUp && cursor.y > 0 {
+        cursor.y -= 1;
+    } else if *command == Command::EditorExitDown {
+        cursor.y += 1;
+    } else if *command == Command::EditorNewLine && cursor.y > 0 {
+        db.insert_empty_row_at(&state.table_name, cursor.y)?;
+        rowid::insert_at(db, &state.table_name, cursor.y, 1)?;
+        let old_text = db
+            .select_at(&state.table_name, cursor.x - 1, cursor.y - 1)?
+            .to_string();
+        let leading_spaces = old_text.chars().take_while(|c| *c == ' ').count();
+        cursor.y += 1;
+        // Set cell to the leading spaces of the old cell
+        let spaces = " ".repeat(leading_spaces);
         db.set_at(
             &state.table_name,
             cursor.y - 1,
             cursor.x - 1,
-            Data::parse(&new_line),
+            Data::String(spaces),
+        )?;
+    }
+    if *command == Command::EditorNewLine && cursor.y == 0 {
+        *mode = Mode::Normal;
+    } else if calendar::is_date_column(db, state, cursor) {
+        calendar::open(db, state, cursor, editor, mode)?;
+    } else {
+        *mode = Mode::Insert;
+        editor_enter(db, state, cursor, editor, -1)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn paste_today(db: &mut Db, state: &State, cursor: &pos::Pos) -> Result<()> {
+    if cursor.y > 0 {
+        extend_table(db, &state.table_name, cursor.x, cursor.y)?;
+        db.set_at(
+            &state.table_name,
+            cursor.y - 1,
+            cursor.x - 1,
+            Data::Date(Date::today()),
         )?;
     }
-    editor.clear();
-    *mode = Mode::Normal;
+    Ok(())
+}
+
+pub(crate) fn paste_now(db: &mut Db, state: &State, cursor: &pos::Pos) -> Result<()> {
+    if cursor.y > 0 {
+        extend_table(db, &state.table_name, cursor.x, cursor.y)?;
+        db.set_at(
+            &state.table_name,
+            cursor.y - 1,
+            cursor.x - 1,
+            Hms::now().to_data(),
+        )?;
+    }
+    Ok(())
+}
+
+// Ctrl-a/Ctrl-x: numbers by amount, dates by days, times by minutes
+pub(crate) fn increment_cell(
+    db: &mut Db,
+    state: &State,
+    cursor: &pos::Pos,
+    amount: isize,
+) -> Result<()> {
+    if cursor.y == 0 || !is_cell(db, state, cursor.x - 1, cursor.y - 1) {
+        return Err("Nothing to increment here".into());
+    }
+    let data = db.select_at(&state.table_name, cursor.x - 1, cursor.y - 1)?;
+    let amount = amount as i64;
+    let new_data = match &data {
+        Data::Int(_) => match data.to_string().parse::<i64>() {
+            Ok(value) => match value.checked_add(amount) {
+                Some(value) => Some(Data::Int(value)),
+                None => return Err(format!("Can't increment '{}', it would overflow", data).into()),
+            },
+            Err(_) => None,
+        },
+        Data::Float(_) => data
+            .to_string()
+            .parse::<f64>()
+            .map(|value| Data::Float(value + amount as f64))
+            .ok(),
+        Data::Date(_) => Ymd::from_data(&data)
+            .and_then(|date| date.add_days(amount))
+            .map(|date| date.to_data()),
+        Data::Time(_) => Hms::from_data(&data).map(|time| time.add_minutes(amount).to_data()),
+        _ => None,
+    };
+    match new_data {
+        Some(new_data) => {
+            let column_types = column_type::get_all(db, &state.table_name);
+            let column_type = column_types.get(cursor.x - 1).and_then(|t| t.as_ref());
+            let new_data = column_type::coerce(column_type, new_data);
+            db.set_at(&state.table_name, cursor.y - 1, cursor.x - 1, new_data)?;
+            Ok(())
+        }
+        None => Err(format!("Can't increment '{}'", data).into()),
+    }
+}
+
+pub(crate) fn insert_empty_column(db: &mut Db, state: &State, cursor: &pos::Pos) -> Result<()> {
+    let mut column_count = db.get_column_count(&state.table_name)?;
+    while column_count < cursor.x {
+        db.create_column(
+            &state.table_name,
+            &generate_column_name(db, &state.table_name, column_count),
+        )?;
+        column_count += 1;
+    }
+    db.insert_column_at(
+        &state.table_name,
+        &generate_column_name(db, &state.table_name, cursor.x),
+        cursor.x - 1,
+    )?;
+    Ok(())
+}
+
+pub(crate) fn insert_empty_row(
+    db: &mut Db,
+    state: &State,
+    cursor: &mut pos::Pos,
+    below: bool,
+) -> Result<()> {
+    if below {
+        if cursor.y > 0 && is_cell(db, state, 0, cursor.y) {
+            db.insert_empty_row_at(&state.table_name, cursor.y)?;
+            rowid::insert_at(db, &state.table_name, cursor.y, 1)?;
+        }
+        cursor.y += 1;
+    } else if cursor.y > 0 && is_cell(db, state, 0, cursor.y - 1) {
+        db.insert_empty_row_at(&state.table_name, cursor.y - 1)?;
+        rowid::insert_at(db, &state.table_name, cursor.y - 1, 1)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn delete_cell(
+    db: &mut Db,
+    state: &State,
+    cursor: &pos::Pos,
+    clipboard_table_name: &str,
+    clipboard: &mut arboard::Clipboard,
+) -> Result<()> {
+    let (x, y) = (cursor.x, cursor.y);
+    yank(
+        Rect {
+            start_x: x,
+            end_x: x + 1,
+            start_y: y,
+            end_y: y + 1,
+        },
+        db,
+        state,
+        clipboard_table_name,
+        clipboard,
+    )?;
+    if cursor.y > 0 {
+        if is_cell(db, state, cursor.x - 1, cursor.y - 1) {
+            db.set_at(&state.table_name, cursor.y - 1, cursor.x - 1, Data::Empty)?;
+        }
+    } else {
+        let old_column_name = db.get_column_name_at(&state.table_name, cursor.x - 1)?;
+        let generic_column_name = generate_column_name(db, &state.table_name, cursor.x - 1);
+        rename_column(
+            db,
+            &state.table_name,
+            &old_column_name,
+            &generic_column_name,
+        )?;
+    }
+    Ok(())
+}
+
+pub(crate) fn indent(
+    db: &mut Db,
+    state: &State,
+    mode: &mut Mode,
+    cursor: &mut pos::Pos,
+    editor: &mut editor::Editor,
+    left: bool,
+) -> Result<()> {
+    editor_enter(db, state, cursor, editor, -1)?;
+    if left {
+        editor.indent_left();
+    } else {
+        editor.indent_right();
+    }
+    editor_exit(db, state, mode, cursor, editor).map_err(|e| {
+        editor.clear();
+        Error::Message(format!("Error in indentation: {}", e))
+    })
+}
+
+// x/y is 1-indexed, r must lie within the table
+fn clear_cells(db: &mut Db, state: &State, r: &Rect) -> Result<()> {
+    for y in r.start_y..r.end_y {
+        for x in r.start_x..r.end_x {
+            db.set_at(&state.table_name, y - 1, x - 1, Data::Empty)?;
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn operate(
+    db: &mut Db,
+    state: &State,
+    mode: &mut Mode,
+    cursor: &mut pos::Pos,
+    editor: &mut editor::Editor,
+    clipboard_table_name: &str,
+    clipboard: &mut arboard::Clipboard,
+    register: Option<char>,
+    operator: Operator,
+    motion: Motion,
+    count: usize,
+) -> Result<()> {
+    // the column header only knows about single cells
+    if cursor.y == 0 && !motion.is_linewise() && motion != Motion::Column {
+        let r = Rect {
+            start_x: cursor.x,
+            end_x: cursor.x + 1,
+            start_y: 0,
+            end_y: 1,
+        };
+        return match operator {
+            Operator::Delete => delete_cell(db, state, cursor, clipboard_table_name, clipboard),
+            Operator::Yank => yank(r, db, state, clipboard_table_name, clipboard),
+            Operator::Change => {
+                *mode = Mode::Insert;
+                editor.insert_at("", 0);
+                Ok(())
+            }
+            Operator::IndentLeft | Operator::IndentRight => Ok(()),
+        };
+    }
+
+    // dd and friends on the header row would hit the first row instead
+    if cursor.y == 0 && operator != Operator::Yank {
+        return Ok(());
+    }
+
+    let (r, kind) = motion::range(db, state, cursor, motion, count);
+    let column_count = db.get_column_count(&state.table_name)?;
+    let row_count = db.get_row_count(&state.table_name)?;
+    // the part of the range that holds data
+    let existing = Rect {
+        start_x: r.start_x,
+        end_x: r.end_x.min(column_count + 1),
+        start_y: r.start_y,
+        end_y: r.end_y.min(row_count + 1),
+    };
+    let has_columns = existing.start_x < existing.end_x;
+    let has_cells = has_columns && existing.start_y < existing.end_y;
+
+    if operator == Operator::IndentLeft || operator == Operator::IndentRight {
+        let xs = if kind == RangeKind::Rows {
+            cursor.x..cursor.x + 1
+        } else {
+            existing.start_x..existing.end_x
+        };
+        let single_cell = xs.len() == 1 && r.end_y - r.start_y == 1;
+        for y in r.start_y..r.end_y {
+            for x in xs.clone() {
+                // a single cell is indented even if it doesn't exist yet
+                if !single_cell
+                    && (!is_cell(db, state, x - 1, y - 1)
+                        || matches!(db.select_at(&state.table_name, x - 1, y - 1)?, Data::Empty))
+                {
+                    continue;
+                }
+                let mut pos = Pos::new(x, y);
+                indent(
+                    db,
+                    state,
+                    mode,
+                    &mut pos,
+                    editor,
+                    operator == Operator::IndentLeft,
+                )?;
+            }
+        }
+        return Ok(());
+    }
+
+    if has_cells {
+        yank(existing, db, state, clipboard_table_name, clipboard)?;
+        registers::store(
+            db,
+            clipboard_table_name,
+            register,
+            operator != Operator::Yank,
+        )?;
+    }
+
+    match operator {
+        Operator::Yank => {}
+        Operator::Delete => match kind {
+            RangeKind::Rows => {
+                for _ in existing.start_y..existing.end_y {
+                    let row_id = rowid::get(db, &state.table_name, existing.start_y)?;
+                    db.delete_row_at(&state.table_name, existing.start_y - 1)?;
+                    rowid::delete_at(db, &state.table_name, existing.start_y - 1)?;
+                    notes::remove_row(db, &state.table_name, row_id)?;
+                }
+                cursor.y = r.start_y;
+                let row_count = db.get_row_count(&state.table_name)?;
+                if cursor.y > 1 && cursor.y > row_count {
+                    cursor.y = row_count.max(1);
+                }
+            }
+            RangeKind::Cells => {
+                if has_cells {
+                    clear_cells(db, state, &existing)?;
+                }
+                cursor.x = r.start_x;
+            }
+            RangeKind::Columns => {
+                if has_columns {
+                    for _ in existing.start_x..existing.end_x {
+                        let column_name =
+                            db.get_column_name_at(&state.table_name, existing.start_x - 1)?;
+                        db.delete_column(&state.table_name, &column_name)?;
+                        settings::remove_column(db, &state.table_name, &column_name)?;
+                        notes::remove_column(db, &state.table_name, &column_name)?;
+                        highlight::clear(db, &state.table_name, &column_name)?;
+                    }
+                }
+                cursor.x = r.start_x;
+            }
+        },
+        Operator::Change => {
+            if has_cells {
+                clear_cells(db, state, &existing)?;
+            }
+            if kind != RangeKind::Rows {
+                cursor.x = r.start_x;
+            }
+            cursor.y = r.start_y;
+            *mode = Mode::Insert;
+            editor.insert_at("", 0);
+        }
+        Operator::IndentLeft | Operator::IndentRight => unreachable!(),
+    }
     Ok(())
 }
 
@@ -255,16 +1257,18 @@ pub(crate) fn yank(
     state: &State,
     clipboard_table_name: &str,
     clipboard: &mut arboard::Clipboard,
-) {
+) -> Result<()> {
     if r.start_y == 0 && r.end_y == 1 {
-        let column_name = get_column_name_or_generic(r.start_x, &*db, &state.table_name);
-        db.create_or_replace_table(clipboard_table_name).unwrap();
-        db.create_column(clipboard_table_name, &column_name)
-            .unwrap();
-        db.insert(clipboard_table_name, vec![&column_name]).unwrap();
+        let column_name = get_column_name_or_generic(r.start_x, &*db, &state.table_name)?;
+        db.create_or_replace_table(clipboard_table_name)?;
+        db.create_column(clipboard_table_name, &column_name)?;
+        db.insert(clipboard_table_name, vec![&column_name])?;
     } else {
-        let columns = db.get_column_names(&state.table_name).unwrap();
+        let columns = db.get_column_names(&state.table_name)?;
         let v2: Vec<&str> = columns.iter().map(|s| &**s).collect();
+        if r.start_x > r.end_x || r.end_x - 1 > v2.len() || r.start_y == 0 {
+            return Err("Nothing to yank".into());
+        }
 
         db.select_into(
             clipboard_table_name,
@@ -272,33 +1276,62 @@ pub(crate) fn yank(
             &v2[(r.start_x - 1)..(r.end_x - 1)],
             r.start_y - 1,
             r.end_y - 1,
-        )
-        .unwrap();
+        )?;
     }
 
-    clipboard_to_clipboard(db, clipboard_table_name, clipboard);
+    clipboard_to_clipboard(db, clipboard_table_name, clipboard)
+}
+
+fn clipboard_text(db: &Db, clipboard_table_name: &str) -> String {
+    let rows: Vec<Vec<String>> = db
+        .select_from(clipboard_table_name)
+        .unwrap_or_default()
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+        .collect();
+    tsv::to_string(&rows)
 }
 
 fn clipboard_to_clipboard(
     db: &mut Db,
     clipboard_table_name: &str,
     clipboard: &mut arboard::Clipboard,
-) {
-    if let Ok(rows) = db.select_from(clipboard_table_name) {
-        let mut clipboard_string = String::new();
-        for (i, row) in rows.iter().enumerate() {
-            if i > 0 {
-                clipboard_string.push('\n');
-            }
-            for (i, cell) in row.iter().enumerate() {
-                if i > 0 {
-                    clipboard_string.push('\t');
-                }
-                clipboard_string.push_str(&cell.to_string());
-            }
+) -> Result<()> {
+    if db.exists(clipboard_table_name) {
+        clipboard.set_text(clipboard_text(db, clipboard_table_name))?;
+    }
+    Ok(())
+}
+
+// replace the clipboard table with text copied in another program, split into cells
+pub(crate) fn clipboard_from_clipboard(
+    db: &mut Db,
+    clipboard_table_name: &str,
+    clipboard: &mut arboard::Clipboard,
+) -> Result<()> {
+    let text = match clipboard.get_text() {
+        Ok(text) => text,
+        Err(_) => return Ok(()),
+    };
+    if text.is_empty()
+        || (db.exists(clipboard_table_name) && text == clipboard_text(db, clipboard_table_name))
+    {
+        return Ok(());
+    }
+    let rows = tsv::parse(&text);
+    let column_count = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    db.create_or_replace_table(clipboard_table_name)?;
+    for idx in 0..column_count {
+        let column_name = generate_column_name(db, clipboard_table_name, idx + 1);
+        db.create_column(clipboard_table_name, &column_name)?;
+    }
+    for (y, row) in rows.iter().enumerate() {
+        db.insert(clipboard_table_name, vec![""; column_count])?;
+        for (x, cell) in row.iter().enumerate() {
+            db.set_at(clipboard_table_name, y, x, Data::parse(cell))?;
         }
-        clipboard.set_text(clipboard_string).unwrap();
     }
+    Ok(())
 }
 
 #[allow(unused_variables)]
@@ -308,11 +1341,14 @@ pub(crate) fn paste(
     clipboard_table_name: &str,
     cursor: &mut pos::Pos,
     command: &Command,
-) {
-    let clip_rows_num = db.get_row_count(clipboard_table_name).unwrap();
-    let clip_cols_num = db.get_column_count(clipboard_table_name).unwrap();
-    let table_rows_num = db.get_row_count(&state.table_name).unwrap();
-    let table_cols_num = db.get_column_count(&state.table_name).unwrap();
+) -> Result<()> {
+    if !db.exists(clipboard_table_name) {
+        return Err("Nothing to paste".into());
+    }
+    let clip_rows_num = db.get_row_count(clipboard_table_name)?;
+    let clip_cols_num = db.get_column_count(clipboard_table_name)?;
+    let table_rows_num = db.get_row_count(&state.table_name)?;
+    let table_cols_num = db.get_column_count(&state.table_name)?;
 
     let paste_overwrite_cells = *command == Command::PasteReplace;
     let paste_insert_cells = !paste_overwrite_cells;
@@ -326,15 +1362,15 @@ pub(crate) fn paste(
     if paste_column_header {
         if let Ok(cell_data) = db.select_at(clipboard_table_name, 0, 0) {
             let new_name = cell_data.to_string();
-            let old_name = db
-                .get_column_name_at(&state.table_name, cursor.x - 1)
-                .unwrap();
-            let check_columns = db.get_column_names(&state.table_name).unwrap();
+            let old_name = db.get_column_name_at(&state.table_name, cursor.x - 1)?;
+            let check_columns = db.get_column_names(&state.table_name)?;
             let new_name = generate_nice_copy_name(&new_name, check_columns);
-            db.rename_column(&state.table_name, &old_name, &new_name)
-                .unwrap();
+            rename_column(db, &state.table_name, &old_name, &new_name)?;
         }
-        return;
+        return Ok(());
+    }
+    if cursor.y == 0 && !insert_columns {
+        return Err("Cannot paste cells into the column header".into());
     }
 
     // calculate paste range, indexs are 0-indexed
@@ -347,37 +1383,55 @@ pub(crate) fn paste(
     };
     let (end_x, end_y) = (start_x + clip_cols_num, start_y + clip_rows_num);
 
-    // TODO: paste all data, not only one cell
+    // 1-indexed like the cursor
+    let pasted = Rect {
+        start_x: start_x + 1,
+        end_x: end_x + 1,
+        start_y: start_y + 1,
+        end_y: end_y + 1,
+    };
     if paste_overwrite_cells {
-        if let Ok(cell_data) = db.select_at(clipboard_table_name, 0, 0) {
-            extend_table(db, &state.table_name, end_x, end_y).unwrap();
-            db.set_at(&state.table_name, start_y, start_x, cell_data)
-                .unwrap();
+        extend_table(db, &state.table_name, end_x, end_y)?;
+        let rows = db.select_from(clipboard_table_name)?;
+        for (dy, row) in rows.iter().enumerate() {
+            for (dx, cell) in row.iter().enumerate() {
+                db.set_at(&state.table_name, start_y + dy, start_x + dx, cell.clone())?;
+            }
         }
+        coerce_cells(db, &state.table_name, &pasted)?;
     } else if insert_rows {
-        let table_column_count = db.get_column_count(&state.table_name).unwrap();
-        let clipboard_column_count = db.get_column_count(clipboard_table_name).unwrap();
+        let table_column_count = db.get_column_count(&state.table_name)?;
+        let clipboard_column_count = db.get_column_count(clipboard_table_name)?;
         if cursor.y > table_rows_num {
             extend_table(
                 db,
                 &state.table_name,
                 0,
                 cursor.y - 1 + usize::from(insert_after),
-            )
-            .unwrap();
+            )?;
         }
-        extend_table(db, &state.table_name, clipboard_column_count, 0).unwrap();
-        extend_table(db, clipboard_table_name, table_column_count, 0).unwrap();
-        db.insert_into_at(clipboard_table_name, &state.table_name, start_y)
-            .unwrap();
+        extend_table(db, &state.table_name, clipboard_column_count, 0)?;
+        extend_table(db, clipboard_table_name, table_column_count, 0)?;
+        db.insert_into_at(clipboard_table_name, &state.table_name, start_y)?;
+        rowid::insert_at(db, &state.table_name, start_y, clip_rows_num)?;
+        let column_count = db.get_column_count(&state.table_name)?;
+        coerce_cells(
+            db,
+            &state.table_name,
+            &Rect {
+                start_x: 1,
+                end_x: column_count + 1,
+                ..pasted
+            },
+        )?;
     } else if insert_columns {
-        let clipboard_row_count = db.get_row_count(clipboard_table_name).unwrap();
-        let table_row_count = db.get_row_count(&state.table_name).unwrap();
-        extend_table(db, &state.table_name, 0, clipboard_row_count).unwrap();
-        extend_table(db, clipboard_table_name, 0, table_row_count).unwrap();
+        let clipboard_row_count = db.get_row_count(clipboard_table_name)?;
+        let table_row_count = db.get_row_count(&state.table_name)?;
+        extend_table(db, &state.table_name, 0, clipboard_row_count)?;
+        extend_table(db, clipboard_table_name, 0, table_row_count)?;
         // make sure column names are unique
-        let table_columns = db.get_column_names(&state.table_name).unwrap();
-        let mut clipboard_columns = db.get_column_names(clipboard_table_name).unwrap();
+        let table_columns = db.get_column_names(&state.table_name)?;
+        let mut clipboard_columns = db.get_column_names(clipboard_table_name)?;
         let old_clipboard_columns = clipboard_columns.clone();
         let mut new_column_names = vec![];
         for column_name in &clipboard_columns.clone() {
@@ -395,13 +1449,21 @@ pub(crate) fn paste(
 
         for (i, column_name) in clipboard_columns.iter_mut().enumerate() {
             if column_name != &old_clipboard_columns[i] {
-                db.rename_column(clipboard_table_name, &old_clipboard_columns[i], column_name)
-                    .unwrap();
+                db.rename_column(clipboard_table_name, &old_clipboard_columns[i], column_name)?;
             }
         }
 
-        db.insert_columns_at(clipboard_table_name, &state.table_name, start_x)
-            .unwrap();
+        db.insert_columns_at(clipboard_table_name, &state.table_name, start_x)?;
+        let row_count = db.get_row_count(&state.table_name)?;
+        coerce_cells(
+            db,
+            &state.table_name,
+            &Rect {
+                start_y: 1,
+                end_y: row_count + 1,
+                ..pasted
+            },
+        )?;
 
         if insert_after {
             if insert_rows {
@@ -413,17 +1475,18 @@ pub(crate) fn paste(
     } else {
         unreachable!("unreachable() reached in paste()");
     }
+    Ok(())
 }
 
 pub fn generate_nice_copy_name(from_name: &str, from_vec: Vec<String>) -> String {
     let check_for_num = |s: &str| -> (usize, u64) {
         let len_utf8 = s.chars().count();
-        let mut index = len_utf8 - 1;
-        let s_nth = |n| s.chars().nth(n).unwrap();
         if s.is_empty() {
             return (len_utf8, 1);
         }
-        if s_nth(index) != ')' {
+        let mut index = len_utf8 - 1;
+        let s_nth = |n| s.chars().nth(n).unwrap();
+        if index == 0 || s_nth(index) != ')' {
             return (len_utf8, 1);
         }
         index -= 1;
@@ -453,6 +1516,26 @@ pub fn generate_nice_copy_name(from_name: &str, from_vec: Vec<String>) -> String
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_expand_date_input() {
+        // plain text in an untyped column stays text
+        for text in ["we", "sa", "fr", "mo", "th", "today", "now", "+1d"] {
+            assert_eq!(expand_date_input(None, &Data::Empty, text), text);
+            let old_data = Data::parse("topic");
+            assert_eq!(expand_date_input(None, &old_data, text), text);
+        }
+        assert_eq!(
+            expand_date_input(Some(&ColumnType::String), &Data::Empty, "today"),
+            "today"
+        );
+        let today = Ymd::today().to_data().to_string();
+        let date = Some(&ColumnType::Date);
+        assert_eq!(expand_date_input(date, &Data::Empty, "today"), today);
+        let old_data = Data::parse("2023-03-08");
+        assert_eq!(expand_date_input(None, &old_data, "today"), today);
+        assert_eq!(expand_date_input(date, &Data::Empty, "weekly"), "weekly");
+    }
+
     #[test]
     fn test_generate_nice_copy_name() {
         let from_name = "test";
@@ -490,5 +1573,11 @@ mod tests {
         ];
         let copy_name = generate_nice_copy_name(from_name, from_vec);
         assert_eq!(copy_name, "test2(2)");
+
+        let copy_name = generate_nice_copy_name("", vec![]);
+        assert_eq!(copy_name, "(2)");
+
+        let copy_name = generate_nice_copy_name(")", vec![]);
+        assert_eq!(copy_name, ")(2)");
     }
 }
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use rzdb::{Data, Db};
+
+use crate::column_type::{self, ColumnType};
+use crate::join;
+use crate::pos::Pos;
+use crate::State;
+
+// values of the column that complete the typed text: prefixes before other matches, then the
+// most frequent values, then the ones used last. The typed text itself isn't offered.
+pub(crate) fn rank(values: &[String], text: &str) -> Vec<String> {
+    let text = text.to_lowercase();
+    // count and last row of each distinct value
+    let mut seen: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (y, value) in values.iter().enumerate() {
+        let entry = seen.entry(value).or_insert((0, y));
+        entry.0 += 1;
+        entry.1 = y;
+    }
+    let mut ranked: Vec<(bool, usize, usize, &str)> = seen
+        .into_iter()
+        .filter_map(|(value, (count, last_y))| {
+            let lower = value.to_lowercase();
+            if lower == text || !lower.contains(&text) {
+                return None;
+            }
+            Some((!lower.starts_with(&text), count, last_y, value))
+        })
+        .collect();
+    ranked.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(b.2.cmp(&a.2)));
+    ranked
+        .into_iter()
+        .map(|(_, _, _, value)| value.to_string())
+        .collect()
+}
+
+// completions while editing a text cell, empty for joined and typed columns
+pub(crate) fn candidates(db: &Db, state: &State, cursor: &Pos, text: &str) -> Vec<String> {
+    if cursor.y == 0 || text.trim().is_empty() {
+        return vec![];
+    }
+    let column_name = match db.get_column_name_at(&state.table_name, cursor.x - 1) {
+        Ok(column_name) => column_name,
+        Err(_) => return vec![],
+    };
+    if join::get(db, &state.table_name, &column_name).is_some() {
+        return vec![];
+    }
+    if !matches!(
+        column_type::get(db, &state.table_name, &column_name),
+        None | Some(ColumnType::String) | Some(ColumnType::Enum(_))
+    ) {
+        return vec![];
+    }
+    // the edited cell doesn't complete itself
+    let values: Vec<String> = (0..db.get_row_count(&state.table_name).unwrap_or(0))
+        .filter(|y| *y != cursor.y - 1)
+        .filter_map(|y| db.select_at(&state.table_name, cursor.x - 1, y).ok())
+        .filter(|data| matches!(data, Data::String(_)))
+        .map(|data| data.to_string())
+        .filter(|value| !value.trim().is_empty())
+        .collect();
+    rank(&values, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank() {
+        let values: Vec<String> = ["work", "home", "homework", "home", "hobby", "Home"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(rank(&values, "ho"), ["home", "Home", "hobby", "homework"]);
+        assert_eq!(rank(&values, "work"), ["homework"]);
+        assert_eq!(rank(&values, "x"), Vec::<String>::new());
+    }
+}
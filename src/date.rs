@@ -0,0 +1,434 @@
+use rzdb::{time::Date, Data};
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+// calendar date for arithmetic and formatting, converted from/to rzdb's Date via its text form
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Ymd {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Ymd {
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Ymd> {
+        if (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month) {
+            Some(Ymd { year, month, day })
+        } else {
+            None
+        }
+    }
+
+    // 2022-12-31 or 31.12.2022
+    pub fn parse(s: &str) -> Option<Ymd> {
+        let s = s.trim();
+        let numbers = |sep: char| -> Option<Vec<i32>> {
+            s.split(sep).map(|part| part.parse::<i32>().ok()).collect()
+        };
+        if let Some(parts) = numbers('-') {
+            if parts.len() == 3 && parts[1] > 0 && parts[2] > 0 {
+                return Ymd::new(parts[0], parts[1] as u32, parts[2] as u32);
+            }
+        }
+        if let Some(parts) = numbers('.') {
+            if parts.len() == 3 && parts[0] > 0 && parts[1] > 0 {
+                return Ymd::new(parts[2], parts[1] as u32, parts[0] as u32);
+            }
+        }
+        None
+    }
+
+    pub fn from_data(data: &Data) -> Option<Ymd> {
+        match data {
+            Data::Date(date) => Ymd::parse(&date.to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn today() -> Ymd {
+        Ymd::parse(&Date::today().to_string()).unwrap_or(Ymd {
+            year: 1970,
+            month: 1,
+            day: 1,
+        })
+    }
+
+    pub fn to_data(self) -> Data {
+        let iso = self.format("%Y-%m-%d");
+        for text in [iso.clone(), self.format("%d.%m.%Y")] {
+            let data = Data::parse(&text);
+            if matches!(data, Data::Date(_)) {
+                return data;
+            }
+        }
+        Data::String(iso)
+    }
+
+    // days since 1970-01-01
+    pub fn days(&self) -> i64 {
+        let y = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let m = i64::from(self.month);
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146097 + doe - 719468
+    }
+
+    // None when the year doesn't fit
+    pub fn from_days(days: i64) -> Option<Ymd> {
+        let z = days.checked_add(719468)?;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = i32::try_from(yoe + era * 400 + i64::from(month <= 2)).ok()?;
+        Some(Ymd { year, month, day })
+    }
+
+    pub fn add_days(&self, days: i64) -> Option<Ymd> {
+        Ymd::from_days(self.days().checked_add(days)?)
+    }
+
+    // the day is clamped to the length of the target month
+    pub fn add_months(&self, months: i64) -> Option<Ymd> {
+        let idx = (i64::from(self.year) * 12 + i64::from(self.month) - 1).checked_add(months)?;
+        let year = i32::try_from(idx.div_euclid(12)).ok()?;
+        let month = idx.rem_euclid(12) as u32 + 1;
+        Some(Ymd {
+            year,
+            month,
+            day: self.day.min(days_in_month(year, month)),
+        })
+    }
+
+    // 0 is Monday
+    pub fn weekday(&self) -> u32 {
+        (self.days() + 3).rem_euclid(7) as u32
+    }
+
+    // %Y %y %m %d %e %B %b %A %a %j %%, other characters are copied
+    pub fn format(&self, fmt: &str) -> String {
+        let mut out = String::new();
+        let mut chars = fmt.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '%' {
+                out.push(ch);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => out += &format!("{:04}", self.year),
+                Some('y') => out += &format!("{:02}", self.year.rem_euclid(100)),
+                Some('m') => out += &format!("{:02}", self.month),
+                Some('d') => out += &format!("{:02}", self.day),
+                Some('e') => out += &self.day.to_string(),
+                Some('B') => out += MONTH_NAMES[self.month as usize - 1],
+                Some('b') => out += &MONTH_NAMES[self.month as usize - 1][..3],
+                Some('A') => out += WEEKDAY_NAMES[self.weekday() as usize],
+                Some('a') => out += &WEEKDAY_NAMES[self.weekday() as usize][..3],
+                Some('j') => {
+                    let first = Ymd::new(self.year, 1, 1).unwrap();
+                    out += &format!("{:03}", self.days() - first.days() + 1)
+                }
+                Some('%') => out.push('%'),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        }
+        out
+    }
+
+    // "today", "yesterday", "in 3 days", "5 days ago"
+    pub fn relative_to(&self, today: &Ymd) -> String {
+        match self.days() - today.days() {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            -1 => "yesterday".to_string(),
+            n if n > 0 => format!("in {} days", n),
+            n => format!("{} days ago", -n),
+        }
+    }
+}
+
+// at least three letters, so that words like "we" or "sa" stay words
+fn weekday_from_name(name: &str) -> Option<u32> {
+    if name.len() < 3 {
+        return None;
+    }
+    WEEKDAY_NAMES
+        .iter()
+        .position(|weekday| weekday.to_lowercase().starts_with(name))
+        .map(|idx| idx as u32)
+}
+
+// today, tomorrow, yesterday, +3d, -2w, +1m, +1y, fri (today or the next friday),
+// next fri, last fri. None when the date is out of range
+pub fn parse_expression(text: &str, today: Ymd) -> Option<Ymd> {
+    let text = text.trim().to_lowercase();
+    match text.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.add_days(1),
+        "yesterday" => return today.add_days(-1),
+        _ => {}
+    }
+    if let Some(rest) = text.strip_prefix(['+', '-']) {
+        let unit = rest.chars().last()?;
+        let amount: i64 = rest[..rest.len() - unit.len_utf8()].parse().ok()?;
+        let amount = if text.starts_with('-') {
+            -amount
+        } else {
+            amount
+        };
+        return match unit {
+            'd' => today.add_days(amount),
+            'w' => today.add_days(amount.checked_mul(7)?),
+            'm' => today.add_months(amount),
+            'y' => today.add_months(amount.checked_mul(12)?),
+            _ => None,
+        };
+    }
+    let (direction, name) = match text.split_once(' ') {
+        Some(("next", name)) => (1, name),
+        Some(("last", name)) => (-1, name),
+        Some(_) => return None,
+        None => (0, text.as_str()),
+    };
+    let weekday = weekday_from_name(name)?;
+    let days_ahead = (i64::from(weekday) - i64::from(today.weekday())).rem_euclid(7);
+    match direction {
+        1 if days_ahead == 0 => today.add_days(7),
+        -1 => today.add_days(days_ahead - 7),
+        _ => today.add_days(days_ahead),
+    }
+}
+
+pub fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+// time of day, converted from/to rzdb's Time via its text form
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Hms {
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl Hms {
+    // 12:30 or 12:30:15
+    pub fn parse(s: &str) -> Option<Hms> {
+        let parts: Option<Vec<u32>> = s.trim().split(':').map(|p| p.parse().ok()).collect();
+        match parts?.as_slice() {
+            [hour, minute] if *hour < 24 && *minute < 60 => Some(Hms {
+                hour: *hour,
+                minute: *minute,
+                second: 0,
+            }),
+            [hour, minute, second] if *hour < 24 && *minute < 60 && *second < 60 => Some(Hms {
+                hour: *hour,
+                minute: *minute,
+                second: *second,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn from_data(data: &Data) -> Option<Hms> {
+        match data {
+            Data::Time(time) => Hms::parse(&time.to_string()),
+            _ => None,
+        }
+    }
+
+    // the time of day on the local clock, std only knows utc, so the time zone comes from
+    // the c library like for date(1)
+    pub fn now() -> Hms {
+        // SAFETY: localtime_r only writes to the tm it's given, all zeros is a valid tm
+        let tm = unsafe {
+            let mut tm: libc::tm = std::mem::zeroed();
+            let now = libc::time(std::ptr::null_mut());
+            if libc::localtime_r(&now, &mut tm).is_null() {
+                return Hms {
+                    hour: 0,
+                    minute: 0,
+                    second: 0,
+                };
+            }
+            tm
+        };
+        Hms {
+            hour: tm.tm_hour as u32,
+            minute: tm.tm_min as u32,
+            second: tm.tm_sec.min(59) as u32,
+        }
+    }
+
+    pub fn to_data(self) -> Data {
+        for text in [self.format("%H:%M:%S"), self.format("%H:%M")] {
+            let data = Data::parse(&text);
+            if matches!(data, Data::Time(_)) {
+                return data;
+            }
+        }
+        Data::String(self.format("%H:%M:%S"))
+    }
+
+    pub fn seconds(&self) -> u32 {
+        self.hour * 3600 + self.minute * 60 + self.second
+    }
+
+    // wraps around midnight
+    pub fn add_minutes(&self, minutes: i64) -> Hms {
+        let minutes = minutes.rem_euclid(24 * 60);
+        let seconds = (i64::from(self.seconds()) + minutes * 60).rem_euclid(24 * 3600) as u32;
+        Hms {
+            hour: seconds / 3600,
+            minute: seconds / 60 % 60,
+            second: seconds % 60,
+        }
+    }
+
+    // %H %M %S %%, other characters are copied
+    pub fn format(&self, fmt: &str) -> String {
+        let mut out = String::new();
+        let mut chars = fmt.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '%' {
+                out.push(ch);
+                continue;
+            }
+            match chars.next() {
+                Some('H') => out += &format!("{:02}", self.hour),
+                Some('M') => out += &format!("{:02}", self.minute),
+                Some('S') => out += &format!("{:02}", self.second),
+                Some('%') => out.push('%'),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        let date = Ymd::new(2022, 12, 31).unwrap();
+        assert_eq!(Ymd::parse("2022-12-31"), Some(date));
+        assert_eq!(Ymd::parse("31.12.2022"), Some(date));
+        assert_eq!(Ymd::parse("2022-02-30"), None);
+        assert_eq!(Ymd::parse("12:30"), None);
+        assert_eq!(Ymd::parse("topic"), None);
+    }
+
+    #[test]
+    fn test_date_arithmetic() {
+        let date = Ymd::new(2024, 2, 28).unwrap();
+        assert_eq!(Ymd::new(1970, 1, 1).unwrap().days(), 0);
+        assert_eq!(Ymd::from_days(date.days()), Some(date));
+        assert_eq!(date.add_days(1), Ymd::new(2024, 2, 29));
+        assert_eq!(date.add_days(2), Ymd::new(2024, 3, 1));
+        assert_eq!(date.add_days(-59), Ymd::new(2023, 12, 31));
+        let date = Ymd::new(2023, 1, 31).unwrap();
+        assert_eq!(date.add_months(1), Ymd::new(2023, 2, 28));
+        assert_eq!(date.add_months(-2), Ymd::new(2022, 11, 30));
+        assert_eq!(date.add_months(12), Ymd::new(2024, 1, 31));
+        // 2023-01-02 was a Monday
+        assert_eq!(Ymd::new(2023, 1, 2).unwrap().weekday(), 0);
+        assert_eq!(Ymd::new(2023, 1, 1).unwrap().weekday(), 6);
+    }
+
+    #[test]
+    fn test_format_date() {
+        let date = Ymd::new(2023, 3, 5).unwrap();
+        assert_eq!(date.format("%Y-%m-%d"), "2023-03-05");
+        assert_eq!(date.format("%d.%m."), "05.03.");
+        assert_eq!(date.format("%a %e %b %y"), "Sun 5 Mar 23");
+        assert_eq!(date.format("100%%"), "100%");
+        let today = Ymd::new(2023, 3, 8).unwrap();
+        assert_eq!(date.relative_to(&today), "3 days ago");
+        assert_eq!(today.relative_to(&date), "in 3 days");
+        assert_eq!(date.add_days(1).unwrap().relative_to(&date), "tomorrow");
+    }
+
+    #[test]
+    fn test_parse_expression() {
+        // a Wednesday
+        let today = Ymd::new(2023, 3, 8).unwrap();
+        let date = |y, m, d| Some(Ymd::new(y, m, d).unwrap());
+        assert_eq!(parse_expression("Today", today), Some(today));
+        assert_eq!(parse_expression("tomorrow", today), date(2023, 3, 9));
+        assert_eq!(parse_expression("+3d", today), date(2023, 3, 11));
+        assert_eq!(parse_expression("-2w", today), date(2023, 2, 22));
+        assert_eq!(parse_expression("+1m", today), date(2023, 4, 8));
+        assert_eq!(parse_expression("fri", today), date(2023, 3, 10));
+        assert_eq!(parse_expression("wed", today), Some(today));
+        assert_eq!(parse_expression("next wednesday", today), date(2023, 3, 15));
+        assert_eq!(parse_expression("last fri", today), date(2023, 3, 3));
+        assert_eq!(parse_expression("last wed", today), date(2023, 3, 1));
+        assert_eq!(parse_expression("+3", today), None);
+        assert_eq!(parse_expression("-", today), None);
+        assert_eq!(parse_expression("free", today), None);
+        assert_eq!(parse_expression("we", today), None);
+        assert_eq!(parse_expression("sa", today), None);
+        assert_eq!(parse_expression("next topic", today), None);
+        assert_eq!(parse_expression("+9223372036854775807d", today), None);
+        assert_eq!(parse_expression("-9223372036854775807w", today), None);
+        assert_eq!(parse_expression("+999999999999y", today), None);
+        assert_eq!(parse_expression("+999999999999m", today), None);
+    }
+
+    #[test]
+    fn test_time() {
+        let time = Hms::parse("08:05").unwrap();
+        assert_eq!(time.format("%H:%M:%S"), "08:05:00");
+        assert_eq!(Hms::parse("24:00"), None);
+        assert_eq!(Hms::parse("2023-01-01"), None);
+        assert_eq!(time.add_minutes(-10).format("%H:%M"), "07:55");
+        assert_eq!(time.add_minutes(-490).format("%H:%M"), "23:55");
+    }
+}
@@ -1,8 +1,46 @@
 use arboard::Clipboard;
+use unicode_segmentation::UnicodeSegmentation;
 
+use crate::error::Result;
+use crate::text;
+
+const LONG_LINE: usize = 40;
+const KILL_RING_SIZE: usize = 20;
+
+// runs of typing or deleting are undone at once
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Typing,
+    Deleting,
+    Other,
+}
+
+// readline style word classes, a word is a run of graphemes of one class
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Space,
+    Word,
+    Punctuation,
+}
+
+fn class(grapheme: &str) -> Class {
+    match grapheme.chars().next() {
+        Some(ch) if ch.is_whitespace() => Class::Space,
+        Some(ch) if ch.is_alphanumeric() || ch == '_' => Class::Word,
+        _ => Class::Punctuation,
+    }
+}
+
+// the cursor counts grapheme clusters, so that an accented letter or an emoji is one step
 pub struct Editor {
     pub line: String,
     pub cur_x: usize,
+    pub picked: Option<usize>,  // highlighted entry of the join picker
+    undo: Vec<(String, usize)>, // text and cursor before each change of this edit session
+    redo: Vec<(String, usize)>,
+    last_edit: Option<(Edit, usize)>, // kind of the last change and the cursor after it
+    kill_ring: Vec<String>,           // deleted text, kept across edit sessions
+    yanked: Option<(usize, usize)>,   // start and kill ring entry of the last Ctrl-y
 }
 
 impl Editor {
@@ -10,15 +48,144 @@ impl Editor {
         Editor {
             line: "".to_string(),
             cur_x: 0,
+            picked: None,
+            undo: vec![],
+            redo: vec![],
+            last_edit: None,
+            kill_ring: vec![],
+            yanked: None,
+        }
+    }
+
+    // apply a change to the text and remember the text before it for undo
+    fn change(&mut self, edit: Edit, f: impl FnOnce(&mut Editor)) {
+        let before = (self.line.clone(), self.cur_x);
+        self.yanked = None;
+        f(self);
+        if self.line == before.0 {
+            return;
+        }
+        if edit == Edit::Other || self.last_edit != Some((edit, before.1)) {
+            self.undo.push(before);
+            self.redo.clear();
+        }
+        self.last_edit = (edit != Edit::Other).then_some((edit, self.cur_x));
+    }
+
+    pub fn undo(&mut self) {
+        if let Some((line, cur_x)) = self.undo.pop() {
+            self.redo
+                .push((std::mem::replace(&mut self.line, line), self.cur_x));
+            self.cur_x = cur_x;
+            self.last_edit = None;
+            self.yanked = None;
         }
     }
 
-    pub fn len_utf8(&self) -> usize {
-        self.line.chars().count()
+    pub fn redo(&mut self) {
+        if let Some((line, cur_x)) = self.redo.pop() {
+            self.undo
+                .push((std::mem::replace(&mut self.line, line), self.cur_x));
+            self.cur_x = cur_x;
+            self.last_edit = None;
+            self.yanked = None;
+        }
+    }
+
+    fn kill(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        self.kill_ring.push(text);
+        if self.kill_ring.len() > KILL_RING_SIZE {
+            self.kill_ring.remove(0);
+        }
+    }
+
+    // Ctrl-y: insert the last deleted text
+    pub fn yank(&mut self) {
+        if let Some(text) = self.kill_ring.last().cloned() {
+            let start = self.cur_x;
+            self.change(Edit::Other, |editor| editor.insert_text(&text));
+            self.yanked = Some((start, self.kill_ring.len() - 1));
+        }
     }
 
-    fn cur_x_bytes(&self) -> usize {
-        self.line.chars().take(self.cur_x).collect::<String>().len()
+    // Alt-y right after Ctrl-y: replace the yanked text with the kill before it
+    pub fn yank_pop(&mut self) {
+        let Some((start, idx)) = self.yanked else {
+            return;
+        };
+        let len = self.kill_ring[idx].graphemes(true).count();
+        if self.cur_x != start + len || self.kill_ring.len() < 2 {
+            return;
+        }
+        let idx = (idx + self.kill_ring.len() - 1) % self.kill_ring.len();
+        let text = self.kill_ring[idx].clone();
+        self.change(Edit::Other, |editor| {
+            editor.remove_range(start, start + len);
+            editor.insert_text(&text);
+        });
+        self.yanked = Some((start, idx));
+    }
+
+    // a combining mark typed after a letter joins its grapheme
+    fn insert_text(&mut self, s: &str) {
+        let idx = self.byte_idx(self.cur_x);
+        self.line.insert_str(idx, s);
+        self.cur_x = self.line[..idx + s.len()].graphemes(true).count();
+    }
+
+    // graphemes start..end, the cursor moves to start
+    fn remove_range(&mut self, start: usize, end: usize) -> String {
+        let range = self.byte_idx(start)..self.byte_idx(end);
+        let removed = self.line[range.clone()].to_string();
+        self.line.replace_range(range, "");
+        self.cur_x = start;
+        removed
+    }
+
+    // entry of the join picker taken on Enter, the best match once something is typed
+    pub fn highlighted(&self) -> Option<usize> {
+        match self.picked {
+            None if self.line.is_empty() => None,
+            picked => Some(picked.unwrap_or(0)),
+        }
+    }
+
+    fn graphemes(&self) -> Vec<&str> {
+        self.line.graphemes(true).collect()
+    }
+
+    fn grapheme_count(&self) -> usize {
+        self.line.graphemes(true).count()
+    }
+
+    // byte offset of the grapheme idx, the length of the line past the end
+    fn byte_idx(&self, idx: usize) -> usize {
+        self.line
+            .grapheme_indices(true)
+            .nth(idx)
+            .map_or(self.line.len(), |(byte_idx, _)| byte_idx)
+    }
+
+    // terminal columns of the text
+    pub fn width(&self) -> usize {
+        text::width(&self.line)
+    }
+
+    // terminal columns left of the cursor, on its row
+    pub fn cursor_width(&self) -> usize {
+        let before = &self.line[..self.byte_idx(self.cur_x)];
+        text::width(before.rsplit('\n').next().unwrap_or_default())
+    }
+
+    // the grapheme under the cursor, a space at the end of a row
+    pub fn cursor_grapheme(&self) -> String {
+        match self.graphemes().get(self.cur_x) {
+            Some(grapheme) if *grapheme != "\n" => grapheme.to_string(),
+            _ => " ".to_string(),
+        }
     }
 
     pub fn left(&mut self) {
@@ -28,16 +195,19 @@ impl Editor {
     }
 
     pub fn right(&mut self) {
-        if self.cur_x < self.line.chars().count() {
+        if self.cur_x < self.grapheme_count() {
             self.cur_x += 1;
         }
     }
 
     pub fn indent_left(&mut self) {
+        self.change(Edit::Other, Editor::unindent);
+    }
+
+    fn unindent(&mut self) {
         let indent_size = 2;
-        let s: String = self.line.chars().take(indent_size).collect();
-        for i in s.chars() {
-            if i != ' ' {
+        for _ in 0..indent_size {
+            if !self.line.starts_with(' ') {
                 return;
             }
             self.line.remove(0);
@@ -49,125 +219,396 @@ impl Editor {
 
     pub fn indent_right(&mut self) {
         let indent_size = 2;
-        self.line.insert_str(0, &" ".repeat(indent_size));
-        self.cur_x += indent_size;
+        self.change(Edit::Other, |editor| {
+            editor.line.insert_str(0, &" ".repeat(indent_size));
+            editor.cur_x += indent_size;
+        });
     }
 
-    pub fn word_left(&mut self) {
-        if self.cur_x > 0 {
-            let mut i = self.cur_x;
-            while i > 0 && self.line.chars().nth(i - 1).unwrap().is_whitespace() {
-                i -= 1;
-            }
-            while i > 0 && !self.line.chars().nth(i - 1).unwrap().is_whitespace() {
+    fn classes(&self) -> Vec<Class> {
+        self.line.graphemes(true).map(class).collect()
+    }
+
+    // start of the word left of the cursor
+    fn word_start(&self) -> usize {
+        let classes = self.classes();
+        let mut i = self.cur_x.min(classes.len());
+        while i > 0 && classes[i - 1] == Class::Space {
+            i -= 1;
+        }
+        if let Some(word) = i.checked_sub(1).map(|i| classes[i]) {
+            while i > 0 && classes[i - 1] == word {
                 i -= 1;
             }
-            self.cur_x = i;
         }
+        i
     }
 
-    pub fn word_right(&mut self) {
-        if self.cur_x < self.line.chars().count() {
-            let mut i = self.cur_x;
-            while i < self.line.chars().count()
-                && !self.line.chars().nth(i).unwrap().is_whitespace()
-            {
+    // end of the word right of the cursor
+    fn word_end(&self) -> usize {
+        let classes = self.classes();
+        let mut i = self.cur_x;
+        while i < classes.len() && classes[i] == Class::Space {
+            i += 1;
+        }
+        if let Some(word) = classes.get(i).copied() {
+            while i < classes.len() && classes[i] == word {
                 i += 1;
             }
-            while i < self.line.chars().count() && self.line.chars().nth(i).unwrap().is_whitespace()
-            {
+        }
+        i
+    }
+
+    // start of the next word, like vim's w
+    fn next_word_start(&self) -> usize {
+        let classes = self.classes();
+        let mut i = self.cur_x;
+        if let Some(word) = classes.get(i).copied() {
+            while i < classes.len() && classes[i] == word {
                 i += 1;
             }
-            self.cur_x = i;
+        }
+        while i < classes.len() && classes[i] == Class::Space {
+            i += 1;
+        }
+        i
+    }
+
+    // Ctrl-g, Alt-b
+    pub fn word_left(&mut self) {
+        self.cur_x = self.word_start();
+    }
+
+    // Ctrl-l
+    pub fn word_right(&mut self) {
+        self.cur_x = self.next_word_start();
+    }
+
+    // Alt-f
+    pub fn word_forward(&mut self) {
+        self.cur_x = self.word_end();
+    }
+
+    // swap the graphemes left and right of the cursor, at the end of a row the last two
+    pub fn transpose(&mut self) {
+        let graphemes = self.graphemes();
+        let at_row_end = graphemes.get(self.cur_x).is_none_or(|g| *g == "\n");
+        let right = if at_row_end {
+            self.cur_x
+        } else {
+            self.cur_x + 1
+        };
+        if right < 2 || graphemes[right - 2] == "\n" || graphemes[right - 1] == "\n" {
+            return;
+        }
+        let swapped = format!("{}{}", graphemes[right - 1], graphemes[right - 2]);
+        self.change(Edit::Other, |editor| {
+            editor.remove_range(right - 2, right);
+            editor.insert_text(&swapped);
+        });
+    }
+
+    // row and column of the cursor in graphemes, rows are separated by line breaks
+    pub fn row_col(&self) -> (usize, usize) {
+        let graphemes = self.graphemes();
+        let before = &graphemes[..self.cur_x.min(graphemes.len())];
+        let row = before.iter().filter(|g| **g == "\n").count();
+        let col = before.iter().rev().take_while(|g| **g != "\n").count();
+        (row, col)
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line.split('\n').count()
+    }
+
+    // the column is clamped to the length of the row
+    fn set_row_col(&mut self, row: usize, col: usize) {
+        let mut idx = 0;
+        for (line_y, text) in self.line.split('\n').enumerate() {
+            let len = text.graphemes(true).count();
+            if line_y == row {
+                self.cur_x = idx + col.min(len);
+                return;
+            }
+            idx += len + 1;
+        }
+    }
+
+    pub fn up(&mut self) {
+        let (row, col) = self.row_col();
+        if row > 0 {
+            self.set_row_col(row - 1, col);
+        }
+    }
+
+    pub fn down(&mut self) {
+        let (row, col) = self.row_col();
+        if row + 1 < self.line_count() {
+            self.set_row_col(row + 1, col);
         }
     }
 
     pub fn home(&mut self) {
-        self.cur_x = 0;
+        self.cur_x -= self.row_col().1;
     }
 
     pub fn end(&mut self) {
-        self.cur_x = self.line.chars().count();
+        self.set_row_col(self.row_col().0, usize::MAX);
     }
 
-    pub fn add(&mut self, ch: char) {
-        let s = match ch {
+    // Alt-Enter
+    pub fn newline(&mut self) {
+        self.change(Edit::Other, |editor| editor.insert_text("\n"));
+    }
+
+    // text with line breaks or too long for the cell is edited in a popup window
+    pub fn is_long(&self) -> bool {
+        self.line.contains('\n') || self.width() > LONG_LINE
+    }
+
+    // the text broken into screen lines of at most width columns, and the screen
+    // row and column of the cursor, which may be just right of a full line
+    pub fn wrapped(&self, width: usize) -> (Vec<String>, (usize, usize)) {
+        let width = width.max(2);
+        let mut lines = vec![];
+        let mut cursor = (0, 0);
+        let mut idx = 0;
+        for text in self.line.split('\n') {
+            let mut line = String::new();
+            for grapheme in text.graphemes(true) {
+                if text::width(&line) + text::width(grapheme) > width {
+                    lines.push(std::mem::take(&mut line));
+                }
+                if idx == self.cur_x {
+                    cursor = (lines.len(), text::width(&line));
+                }
+                line += grapheme;
+                idx += 1;
+            }
+            if idx == self.cur_x {
+                cursor = (lines.len(), text::width(&line));
+            }
+            lines.push(line);
+            idx += 1; // the line break
+        }
+        (lines, cursor)
+    }
+
+    fn typed(ch: char) -> String {
+        match ch {
             '\t' => "    ".to_string(),
             '\r' => " ".to_string(),
             '\n' => " ".to_string(),
             _ => ch.to_string(),
+        }
+    }
+
+    // a word typed is undone at once
+    pub fn add(&mut self, ch: char) {
+        let edit = if ch.is_whitespace() {
+            Edit::Other
+        } else {
+            Edit::Typing
         };
-        self.line.insert_str(self.cur_x_bytes(), &s);
-        self.cur_x += s.chars().count();
+        self.change(edit, |editor| editor.insert_text(&Editor::typed(ch)));
     }
 
+    // ends the edit session, the kill ring is kept
     pub fn clear(&mut self) {
         self.line.clear();
         self.cur_x = 0;
+        self.picked = None;
+        self.undo.clear();
+        self.redo.clear();
+        self.last_edit = None;
+        self.yanked = None;
     }
 
+    // replace the whole text, e. g. with a completion
+    pub fn replace(&mut self, text: &str) {
+        self.change(Edit::Other, |editor| {
+            editor.line = text.to_string();
+            editor.cur_x = editor.grapheme_count();
+        });
+    }
+
+    // starts an edit session with the given text, the cursor before grapheme idx
     pub fn insert_at(&mut self, old_text: &str, idx: usize) {
-        self.line.clear();
+        self.clear();
         self.line.push_str(old_text);
-        self.cur_x = idx.min(self.line.chars().count());
+        self.cur_x = idx.min(self.grapheme_count());
     }
 
     pub fn backspace(&mut self) {
         if self.cur_x > 0 {
-            self.cur_x -= 1;
-            self.line.remove(self.cur_x_bytes());
+            self.change(Edit::Deleting, |editor| {
+                editor.remove_range(editor.cur_x - 1, editor.cur_x);
+            });
         }
     }
 
+    // nothing to delete at the end of the text
     pub fn delete(&mut self) {
-        self.line.remove(self.cur_x_bytes());
+        if self.cur_x < self.grapheme_count() {
+            self.change(Edit::Deleting, |editor| {
+                editor.remove_range(editor.cur_x, editor.cur_x + 1);
+            });
+        }
     }
 
+    // Ctrl-u, Ctrl-k, Ctrl-w and Alt-d feed the kill ring
     pub fn delete_left_all(&mut self) {
-        while self.cur_x > 0 {
-            self.backspace();
-        }
+        let start = self.cur_x - self.row_col().1;
+        self.kill_range(start, self.cur_x);
     }
 
     pub fn delete_right_all(&mut self) {
-        while self.cur_x < self.line.chars().count() {
-            self.delete();
-        }
+        let len = self
+            .graphemes()
+            .iter()
+            .skip(self.cur_x)
+            .take_while(|g| **g != "\n")
+            .count();
+        self.kill_range(self.cur_x, self.cur_x + len);
     }
 
     pub fn delete_word(&mut self) {
-        while self.cur_x > 0
-            && self
-                .line
-                .chars()
-                .nth(self.cur_x - 1)
-                .unwrap()
-                .is_whitespace()
-        {
-            self.backspace();
-        }
-        while self.cur_x > 0
-            && !self
-                .line
-                .chars()
-                .nth(self.cur_x - 1)
-                .unwrap()
-                .is_whitespace()
-        {
-            self.backspace();
-        }
+        self.kill_range(self.word_start(), self.cur_x);
+    }
+
+    // Alt-d
+    pub fn delete_word_forward(&mut self) {
+        self.kill_range(self.cur_x, self.word_end());
+    }
+
+    fn kill_range(&mut self, start: usize, end: usize) {
+        let mut removed = String::new();
+        self.change(Edit::Other, |editor| {
+            removed = editor.remove_range(start, end);
+        });
+        self.kill(removed);
     }
 
     pub fn get_line(&self) -> String {
         self.line.clone()
     }
 
-    pub fn insert_clipboard(&mut self) {
-        let mut clipboard = Clipboard::new().unwrap();
-        if let Ok(text) = clipboard.get_text() {
-            for s in text.chars() {
-                self.add(s);
-            }
+    pub fn insert_clipboard(&mut self) -> Result<()> {
+        let text = Clipboard::new()?.get_text()?;
+        let text: String = text.chars().map(Editor::typed).collect();
+        self.change(Edit::Other, |editor| editor.insert_text(&text));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multi_line() {
+        let mut editor = Editor::new();
+        editor.insert_at("first\nsecond line", 3);
+        assert_eq!(editor.row_col(), (0, 3));
+        editor.down();
+        assert_eq!(editor.row_col(), (1, 3));
+        editor.end();
+        editor.up();
+        assert_eq!(editor.row_col(), (0, 5));
+        editor.newline();
+        assert_eq!(editor.line, "first\n\nsecond line");
+        assert_eq!(editor.line_count(), 3);
+        editor.insert_at("abcdef\ngh", 6);
+        assert_eq!(
+            editor.wrapped(3),
+            (vec!["abc".into(), "def".into(), "gh".into()], (1, 3))
+        );
+    }
+
+    #[test]
+    fn test_undo_and_kill_ring() {
+        let mut editor = Editor::new();
+        for ch in "one two".chars() {
+            editor.add(ch);
         }
+        editor.undo();
+        assert_eq!(editor.line, "one ");
+        editor.redo();
+        assert_eq!(editor.line, "one two");
+        editor.delete_word();
+        editor.delete_left_all();
+        assert_eq!(editor.line, "");
+        editor.yank();
+        assert_eq!(editor.line, "one ");
+        editor.yank_pop();
+        assert_eq!(editor.line, "two");
+        editor.undo();
+        editor.undo();
+        assert_eq!(editor.line, "");
+        editor.undo();
+        assert_eq!(editor.line, "one ");
+    }
+
+    #[test]
+    fn test_graphemes() {
+        let mut editor = Editor::new();
+        // e with a combining acute accent, a wide character and an emoji
+        editor.insert_at("cafe\u{301} 日本 👍", 0);
+        editor.end();
+        assert_eq!(editor.cur_x, 9);
+        assert_eq!(editor.cursor_width(), 12);
+        editor.backspace();
+        assert_eq!(editor.line, "cafe\u{301} 日本 ");
+        editor.home();
+        editor.word_right();
+        editor.left();
+        assert_eq!(editor.cursor_grapheme(), " ");
+        editor.left();
+        assert_eq!(editor.cursor_grapheme(), "e\u{301}");
+        assert_eq!(editor.cursor_width(), 3);
+        editor.delete();
+        assert_eq!(editor.line, "caf 日本 ");
+        // typing a combining mark joins the letter before it
+        editor.add('e');
+        editor.add('\u{301}');
+        assert_eq!(editor.cur_x, 4);
+        // delete at the end of the text does nothing
+        editor.end();
+        editor.delete();
+        assert_eq!(editor.line, "cafe\u{301} 日本 ");
+        assert_eq!(editor.width(), 10);
+        editor.insert_at("日本語", 3);
+        assert_eq!(
+            editor.wrapped(5),
+            (vec!["日本".into(), "語".into()], (1, 2))
+        );
+    }
+
+    #[test]
+    fn test_words() {
+        let mut editor = Editor::new();
+        editor.insert_at("foo.bar(baz)  qux", 0);
+        editor.word_right();
+        assert_eq!(editor.cur_x, 3);
+        editor.word_right();
+        assert_eq!(editor.cur_x, 4);
+        editor.word_forward();
+        assert_eq!(editor.cur_x, 7);
+        editor.end();
+        editor.word_left();
+        assert_eq!(editor.cur_x, 14);
+        editor.word_left();
+        assert_eq!(editor.cur_x, 11);
+        editor.delete_word();
+        assert_eq!(editor.line, "foo.bar()  qux");
+        editor.home();
+        editor.delete_word_forward();
+        assert_eq!(editor.line, ".bar()  qux");
+        editor.insert_at("ab", 2);
+        editor.transpose();
+        assert_eq!(editor.line, "ba");
+        editor.insert_at("abc", 1);
+        editor.transpose();
+        assert_eq!((editor.line.as_str(), editor.cur_x), ("bac", 2));
     }
 }
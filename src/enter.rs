@@ -0,0 +1,49 @@
+use rzdb::Db;
+
+use crate::error::Result;
+use crate::outline;
+use crate::settings;
+
+const ENTER_KEY: &str = "enter";
+
+// what Enter does when leaving the cell editor, a table wide setting. Shift-Enter does
+// the other one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnterMode {
+    Down,
+    NewRow,
+}
+
+impl EnterMode {
+    pub fn parse(s: &str) -> Option<EnterMode> {
+        match s {
+            "down" => Some(EnterMode::Down),
+            "newrow" => Some(EnterMode::NewRow),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnterMode::Down => "down",
+            EnterMode::NewRow => "newrow",
+        }
+    }
+}
+
+// outlines open a new row on Enter unless set otherwise, other tables move down
+pub(crate) fn get(db: &Db, table_name: &str) -> EnterMode {
+    match settings::get(db, table_name, "", ENTER_KEY).and_then(|s| EnterMode::parse(&s)) {
+        Some(enter_mode) => enter_mode,
+        None if outline::column(db, table_name).is_some() => EnterMode::NewRow,
+        None => EnterMode::Down,
+    }
+}
+
+// None goes back to the default
+pub(crate) fn set(db: &mut Db, table_name: &str, enter_mode: Option<EnterMode>) -> Result<()> {
+    let value = enter_mode
+        .map(|enter_mode| enter_mode.name())
+        .unwrap_or_default();
+    settings::set(db, table_name, "", ENTER_KEY, value)
+}
@@ -0,0 +1,54 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    Db(Box<dyn std::error::Error>),
+    Clipboard(arboard::Error),
+    Io(std::io::Error),
+    Message(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Db(e) => write!(f, "{}", e),
+            Error::Clipboard(e) => write!(f, "Clipboard: {}", e),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Message(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Box<dyn std::error::Error>> for Error {
+    fn from(e: Box<dyn std::error::Error>) -> Error {
+        Error::Db(e)
+    }
+}
+
+impl From<arboard::Error> for Error {
+    fn from(e: arboard::Error) -> Error {
+        Error::Clipboard(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<String> for Error {
+    fn from(msg: String) -> Error {
+        Error::Message(msg)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(msg: &str) -> Error {
+        Error::Message(msg.to_string())
+    }
+}
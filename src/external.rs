@@ -0,0 +1,137 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, stdout, Stdout, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use rzdb::{Data, Db};
+use termion::input::MouseTerminal;
+
+use crate::common::{self, is_cell, Rect};
+use crate::error::Result;
+use crate::pos::Pos;
+use crate::tsv;
+use crate::State;
+
+// a new temp file only we can read, an existing file or symlink with the name is never
+// followed, another name is tried instead
+fn create_temp_file(extension: &str, text: &str) -> Result<PathBuf> {
+    for n in 0..100 {
+        let name = format!("rspread-{}-{}.{}", process::id(), n, extension);
+        let path = std::env::temp_dir().join(name);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&path) {
+            Ok(mut file) => {
+                if let Err(e) = file.write_all(text.as_bytes()) {
+                    let _ = fs::remove_file(&path);
+                    return Err(e.into());
+                }
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err("Can't create a temporary file".into())
+}
+
+// $VISUAL or $EDITOR, which may carry arguments like "code --wait"
+fn run_editor(path: &Path, mouse_terminal: &mut Option<MouseTerminal<Stdout>>) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut args = editor.split_whitespace();
+    let program = args.next().unwrap_or("vi");
+    // the terminal is out of raw mode between two keys, mouse reporting is off while the
+    // editor runs
+    *mouse_terminal = None;
+    let mut stdout = stdout();
+    write!(stdout, "{}", termion::cursor::Show)?;
+    stdout.flush()?;
+    let status = process::Command::new(program).args(args).arg(path).status();
+    *mouse_terminal = Some(MouseTerminal::from(stdout));
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{} exited with {}, nothing changed", editor, status).into()),
+        Err(e) => Err(format!("Can't run {}: {}", editor, e).into()),
+    }
+}
+
+// Ctrl-e: edit the cell under the cursor as plain text, or the selection as tab separated
+// text, in an external editor. The edited text may add rows and columns.
+pub(crate) fn edit(
+    db: &mut Db,
+    state: &State,
+    cursor: &Pos,
+    selection: &Option<Pos>,
+    mouse_terminal: &mut Option<MouseTerminal<Stdout>>,
+) -> Result<()> {
+    let r = match selection {
+        Some(anchor) => Rect::from_corners(anchor, cursor),
+        None => Rect::from_corners(cursor, cursor),
+    };
+    if r.start_y == 0 {
+        return Err("Can't edit the column header in an editor".into());
+    }
+    let single_cell = selection.is_none() || (r.end_x - r.start_x == 1 && r.end_y - r.start_y == 1);
+    let mut rows = vec![];
+    for y in r.start_y..r.end_y {
+        let mut row = vec![];
+        for x in r.start_x..r.end_x {
+            row.push(if is_cell(db, state, x - 1, y - 1) {
+                db.select_at(&state.table_name, x - 1, y - 1)?.to_string()
+            } else {
+                "".to_string()
+            });
+        }
+        rows.push(row);
+    }
+    let (text, extension) = if single_cell {
+        (format!("{}\n", rows[0][0]), "txt")
+    } else {
+        (tsv::to_string(&rows), "tsv")
+    };
+
+    let path = create_temp_file(extension, &text)?;
+    let result = run_editor(&path, mouse_terminal);
+    let new_text = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    result?;
+    let new_text = new_text?;
+
+    let new_rows = if single_cell {
+        let cell = new_text.strip_suffix('\n').unwrap_or(&new_text);
+        vec![vec![cell.strip_suffix('\r').unwrap_or(cell).to_string()]]
+    } else {
+        tsv::parse(&new_text)
+    };
+    for (dy, row) in new_rows.iter().enumerate() {
+        for (dx, cell) in row.iter().enumerate() {
+            let (x, y) = (r.start_x + dx, r.start_y + dy);
+            if !is_cell(db, state, x - 1, y - 1) {
+                if cell.is_empty() {
+                    continue;
+                }
+                common::extend_table(db, &state.table_name, x, y)?;
+            }
+            db.set_at(&state.table_name, y - 1, x - 1, Data::parse(cell))?;
+        }
+    }
+    // lines and cells deleted in the editor are emptied
+    for y in r.start_y..r.end_y {
+        let row_len = new_rows.get(y - r.start_y).map_or(0, |row| row.len());
+        for x in r.start_x + row_len..r.end_x {
+            if is_cell(db, state, x - 1, y - 1) {
+                db.set_at(&state.table_name, y - 1, x - 1, Data::Empty)?;
+            }
+        }
+    }
+    let edited = Rect {
+        end_x: r.start_x + new_rows.iter().map(|row| row.len()).max().unwrap_or(0),
+        end_y: r.start_y + new_rows.len(),
+        ..r
+    };
+    common::coerce_cells(db, &state.table_name, &edited)
+}
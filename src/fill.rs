@@ -0,0 +1,250 @@
+use rzdb::{Data, Db};
+
+use crate::common::{self, Rect};
+use crate::date::Ymd;
+use crate::error::Result;
+use crate::pos::Pos;
+use crate::State;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillMode {
+    Copy,
+    Series,
+    Weekdays,
+    Monthly,
+}
+
+impl FillMode {
+    pub fn parse(s: &str) -> Option<FillMode> {
+        match s {
+            "copy" => Some(FillMode::Copy),
+            "series" => Some(FillMode::Series),
+            "weekdays" => Some(FillMode::Weekdays),
+            "monthly" => Some(FillMode::Monthly),
+            _ => None,
+        }
+    }
+}
+
+fn number(data: &Data) -> Option<f64> {
+    match data {
+        Data::Int(_) | Data::Float(_) => data.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+fn int(data: &Data) -> Option<i64> {
+    match data {
+        Data::Int(_) => data.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+// integer series are computed exactly, cells beyond the range of i64 are left empty
+fn int_series(seeds: &[Data], count: usize) -> Vec<Data> {
+    let start = seeds.first().and_then(int).unwrap_or(0);
+    let step = match seeds.get(1).and_then(int) {
+        Some(second) => second.checked_sub(start),
+        None => Some(1),
+    };
+    let offset = seeds.len() as i64 - 1;
+    (1..=count as i64)
+        .map(|i| {
+            step.and_then(|step| step.checked_mul(offset + i))
+                .and_then(|delta| start.checked_add(delta))
+                .map_or(Data::Empty, |value| Data::parse(&value.to_string()))
+        })
+        .collect()
+}
+
+fn float_data(value: f64) -> Data {
+    // round away float noise like 0.30000000000000004
+    Data::Float((value * 1e9).round() / 1e9)
+}
+
+fn next_weekday(date: Ymd) -> Option<Ymd> {
+    let mut date = date.add_days(1)?;
+    while date.weekday() >= 5 {
+        date = date.add_days(1)?;
+    }
+    Some(date)
+}
+
+// dates out of range are left empty
+fn date_data(date: Option<Ymd>) -> Data {
+    date.map_or(Data::Empty, |date| date.to_data())
+}
+
+// the count cells following the seeds: one seed is copied or counted up by one,
+// two seeds give the step of the series
+pub(crate) fn series(seeds: &[Data], mode: FillMode, count: usize) -> Vec<Data> {
+    let first = match seeds.first() {
+        Some(first) => first.clone(),
+        None => return vec![Data::Empty; count],
+    };
+    let last = seeds.last().unwrap_or(&first).clone();
+    let n = seeds.len() as f64;
+    let steps = (1..=count).map(|i| i as f64);
+    match (mode, &first) {
+        (FillMode::Copy, _) => vec![first; count],
+        (FillMode::Series, Data::Int(_) | Data::Float(_)) => {
+            let is_int = seeds
+                .iter()
+                .all(|data| matches!(data, Data::Int(_)) || number(data).is_none());
+            if is_int {
+                return int_series(seeds, count);
+            }
+            let start = number(&first).unwrap_or(0.0);
+            let step = match seeds.get(1).and_then(number) {
+                Some(second) => second - start,
+                None => 1.0,
+            };
+            steps
+                .map(|i| float_data(start + step * (n - 1.0 + i)))
+                .collect()
+        }
+        (_, Data::Date(_)) => {
+            let (start, last) = match (Ymd::from_data(&first), Ymd::from_data(&last)) {
+                (Some(start), Some(last)) => (start, last),
+                _ => return vec![first; count],
+            };
+            let second = seeds.get(1).and_then(Ymd::from_data);
+            match mode {
+                FillMode::Weekdays => {
+                    let mut date = Some(last);
+                    (0..count)
+                        .map(|_| {
+                            date = date.and_then(next_weekday);
+                            date_data(date)
+                        })
+                        .collect()
+                }
+                FillMode::Monthly => {
+                    let step = second.map_or(1, |second| {
+                        i64::from(second.year - start.year) * 12 + i64::from(second.month)
+                            - i64::from(start.month)
+                    });
+                    let offset = (seeds.len() as i64 - 1).saturating_mul(step);
+                    // always count from the first date, so that the 31st survives February
+                    (1..=count as i64)
+                        .map(|i| {
+                            let months = step.checked_mul(i).and_then(|m| m.checked_add(offset));
+                            date_data(months.and_then(|months| start.add_months(months)))
+                        })
+                        .collect()
+                }
+                _ => {
+                    let step = second.map_or(1, |second| second.days() - start.days());
+                    (1..=count as i64)
+                        .map(|i| {
+                            date_data(step.checked_mul(i).and_then(|days| last.add_days(days)))
+                        })
+                        .collect()
+                }
+            }
+        }
+        _ => vec![first; count],
+    }
+}
+
+// fill the selection, or the cursor column from the cursor down to the last non-empty row,
+// with the top cells copied down or continued as a series
+pub(crate) fn fill(
+    db: &mut Db,
+    state: &State,
+    cursor: &Pos,
+    selection: &Option<Pos>,
+    mode: FillMode,
+) -> Result<()> {
+    let r = match selection {
+        Some(anchor) => Rect::from_corners(anchor, cursor),
+        None => {
+            let rows = db.select_from(&state.table_name)?;
+            let last_row = rows
+                .iter()
+                .rposition(|row| row.iter().any(|data| !matches!(data, Data::Empty)))
+                .map_or(0, |y| y + 1);
+            Rect {
+                start_x: cursor.x,
+                end_x: cursor.x + 1,
+                start_y: cursor.y,
+                end_y: last_row.max(cursor.y) + 1,
+            }
+        }
+    };
+    if r.start_y == 0 {
+        return Err("Can't fill the column header".into());
+    }
+    if r.end_y - r.start_y < 2 {
+        return Err("Nothing to fill, select the rows or add rows below".into());
+    }
+    common::extend_table(db, &state.table_name, r.end_x - 1, r.end_y - 1)?;
+    for x in r.start_x..r.end_x {
+        let cell = |db: &Db, y: usize| db.select_at(&state.table_name, x - 1, y - 1);
+        // a second non-empty cell of the same kind makes the step of a series
+        let first = cell(db, r.start_y)?;
+        let second = cell(db, r.start_y + 1)?;
+        let is_series = mode != FillMode::Copy
+            && r.end_y - r.start_y > 2
+            && std::mem::discriminant(&first) == std::mem::discriminant(&second);
+        let seeds = if is_series {
+            vec![first, second]
+        } else {
+            vec![first]
+        };
+        let start_y = r.start_y + seeds.len();
+        for (idx, data) in series(&seeds, mode, r.end_y - start_y)
+            .into_iter()
+            .enumerate()
+        {
+            db.set_at(&state.table_name, start_y + idx - 1, x - 1, data)?;
+        }
+    }
+    common::coerce_cells(db, &state.table_name, &r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::strings;
+
+    #[test]
+    fn test_series() {
+        let d = |s: &str| Data::parse(s);
+        assert_eq!(strings(&series(&[d("5")], FillMode::Series, 2)), ["6", "7"]);
+        assert_eq!(
+            strings(&series(&[d("1"), d("3")], FillMode::Series, 2)),
+            ["5", "7"]
+        );
+        assert_eq!(
+            strings(&series(&[d("0.1"), d("0.2")], FillMode::Series, 1)),
+            ["0.3"]
+        );
+        assert_eq!(
+            strings(&series(&[d("9007199254740993")], FillMode::Series, 1)),
+            ["9007199254740994"]
+        );
+        assert_eq!(
+            strings(&series(&[d("9223372036854775806")], FillMode::Series, 2)),
+            ["9223372036854775807", ""]
+        );
+        assert_eq!(strings(&series(&[d("x")], FillMode::Series, 2)), ["x", "x"]);
+        assert_eq!(strings(&series(&[d("5")], FillMode::Copy, 1)), ["5"]);
+        assert_eq!(
+            strings(&series(&[d("2023-03-03")], FillMode::Weekdays, 2)),
+            ["2023-03-06", "2023-03-07"]
+        );
+        assert_eq!(
+            strings(&series(&[d("2023-01-31")], FillMode::Monthly, 2)),
+            ["2023-02-28", "2023-03-31"]
+        );
+        assert_eq!(
+            strings(&series(
+                &[d("2023-01-01"), d("2023-01-08")],
+                FillMode::Series,
+                1
+            )),
+            ["2023-01-15"]
+        );
+    }
+}
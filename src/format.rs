@@ -0,0 +1,257 @@
+use rzdb::{Data, Db};
+
+use crate::date::{Hms, Ymd};
+use crate::error::Result;
+use crate::settings;
+
+const FORMAT_KEY: &str = "format";
+// f64 has about 17 significant digits, more decimals only make the cells longer
+const MAX_DECIMALS: usize = 20;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NumberFormat {
+    prefix: String,
+    suffix: String,
+    decimals: usize,
+    thousands_separator: Option<char>,
+    decimal_point: char,
+}
+
+// how the cells of a column are displayed, the editor always shows the raw value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DisplayFormat {
+    Number(NumberFormat),
+    Date(String),
+    Time(String),
+    Relative,
+}
+
+impl NumberFormat {
+    // printf style "%.2f", optionally with text around it, e. g. "%.2f €"
+    fn parse_printf(s: &str) -> Option<NumberFormat> {
+        let start = s.find('%')?;
+        let rest = &s[start + 1..];
+        let end = rest.find('f')?;
+        let spec = &rest[..end];
+        let decimals = match spec.strip_prefix('.') {
+            Some(digits) => digits.parse().ok()?,
+            None if spec.is_empty() => 6,
+            None => return None,
+        };
+        if decimals > MAX_DECIMALS {
+            return None;
+        }
+        Some(NumberFormat {
+            prefix: s[..start].to_string(),
+            suffix: rest[end + 1..].to_string(),
+            decimals,
+            thousands_separator: None,
+            decimal_point: '.',
+        })
+    }
+
+    // an example number, e. g. "1,234.50 €" or "€ 1.234,50" or "0.0"
+    fn parse_example(s: &str) -> Option<NumberFormat> {
+        let is_digit = |ch: char| ch.is_ascii_digit() || ch == '#';
+        let start = s.find(is_digit)?;
+        let end = s.rfind(is_digit)? + 1;
+        let number = &s[start..end];
+        let separators: Vec<(usize, char)> = number
+            .char_indices()
+            .filter(|(_, ch)| !is_digit(*ch))
+            .collect();
+        if separators
+            .iter()
+            .any(|(_, ch)| !matches!(ch, ',' | '.' | '\'' | ' '))
+        {
+            return None;
+        }
+        let digits_after =
+            |idx: usize| number[idx + 1..].chars().filter(|ch| is_digit(*ch)).count();
+        let (thousands_separator, decimal_point, decimals) = match separators.as_slice() {
+            [] => (None, '.', 0),
+            // "1,000" groups thousands, "0,000" and "1,00" have decimals
+            [(idx, ch)]
+                if digits_after(*idx) == 3
+                    && !number[..*idx].trim_start_matches('0').is_empty() =>
+            {
+                (Some(*ch), if *ch == '.' { ',' } else { '.' }, 0)
+            }
+            [(idx, ch)] => (None, *ch, digits_after(*idx)),
+            [.., (idx, last)] => {
+                if separators.iter().all(|(_, ch)| ch == last) {
+                    let decimal_point = if *last == '.' { ',' } else { '.' };
+                    (Some(*last), decimal_point, 0)
+                } else {
+                    (Some(separators[0].1), *last, digits_after(*idx))
+                }
+            }
+        };
+        if decimals > MAX_DECIMALS {
+            return None;
+        }
+        Some(NumberFormat {
+            prefix: s[..start].to_string(),
+            suffix: s[end..].to_string(),
+            decimals,
+            thousands_separator,
+            decimal_point,
+        })
+    }
+
+    fn apply(&self, value: f64) -> String {
+        let text = format!("{:.*}", self.decimals, value.abs());
+        let (int_part, frac_part) = match text.split_once('.') {
+            Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+            None => (text.as_str(), None),
+        };
+        let mut grouped = String::new();
+        for (i, ch) in int_part.chars().enumerate() {
+            if i > 0 && (int_part.len() - i) % 3 == 0 {
+                if let Some(separator) = self.thousands_separator {
+                    grouped.push(separator);
+                }
+            }
+            grouped.push(ch);
+        }
+        if let Some(frac_part) = frac_part {
+            grouped.push(self.decimal_point);
+            grouped.push_str(frac_part);
+        }
+        let sign = if value < 0.0 && text.chars().any(|ch| ('1'..='9').contains(&ch)) {
+            "-"
+        } else {
+            ""
+        };
+        format!("{}{}{}{}", self.prefix, sign, grouped, self.suffix)
+    }
+}
+
+impl DisplayFormat {
+    pub fn parse(s: &str) -> Option<DisplayFormat> {
+        let has = |specs: &str| specs.chars().any(|spec| s.contains(&format!("%{}", spec)));
+        if s == "relative" {
+            Some(DisplayFormat::Relative)
+        } else if has("YymdeBbAaj") {
+            Some(DisplayFormat::Date(s.to_string()))
+        } else if has("HMS") {
+            Some(DisplayFormat::Time(s.to_string()))
+        } else if s.contains('%') {
+            NumberFormat::parse_printf(s).map(DisplayFormat::Number)
+        } else {
+            NumberFormat::parse_example(s).map(DisplayFormat::Number)
+        }
+    }
+
+    // None if the format doesn't apply to the cell's data type
+    pub fn apply(&self, data: &Data) -> Option<String> {
+        match (self, data) {
+            (DisplayFormat::Number(format), Data::Int(_) | Data::Float(_)) => data
+                .to_string()
+                .parse()
+                .ok()
+                .map(|value| format.apply(value)),
+            (DisplayFormat::Date(format), Data::Date(_)) => {
+                Ymd::from_data(data).map(|date| date.format(format))
+            }
+            (DisplayFormat::Relative, Data::Date(_)) => {
+                Ymd::from_data(data).map(|date| date.relative_to(&Ymd::today()))
+            }
+            (DisplayFormat::Time(format), Data::Time(_)) => {
+                Hms::from_data(data).map(|time| time.format(format))
+            }
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn display(data: &Data, format: Option<&DisplayFormat>) -> String {
+    let text = format
+        .and_then(|format| format.apply(data))
+        .unwrap_or_else(|| data.no_time_seconds());
+    first_line(text)
+}
+
+// multi-line cells show their first line and a marker
+pub(crate) fn first_line(text: String) -> String {
+    match text.split_once('\n') {
+        Some((first, _)) => format!("{} ↵", first),
+        None => text,
+    }
+}
+
+pub(crate) fn is_numeric(data: &Data) -> bool {
+    matches!(data, Data::Int(_) | Data::Float(_))
+}
+
+// display format of each column, in column order
+pub(crate) fn get_all(db: &Db, table_name: &str) -> Vec<Option<DisplayFormat>> {
+    settings::get_for_columns(db, table_name, FORMAT_KEY)
+        .iter()
+        .map(|s| s.as_ref().and_then(|s| DisplayFormat::parse(s)))
+        .collect()
+}
+
+// "none" removes the format
+pub(crate) fn set(db: &mut Db, table_name: &str, column_name: &str, format: &str) -> Result<()> {
+    let format = format.trim();
+    if format == "none" || format.is_empty() {
+        return settings::set(db, table_name, column_name, FORMAT_KEY, "");
+    }
+    if DisplayFormat::parse(format).is_none() {
+        return Err(format!(
+            "Unknown format {}, use e. g. %.2f, 1,234.50 €, %Y-%m-%d, %H:%M or relative, \
+             numbers have at most {} decimals",
+            format, MAX_DECIMALS
+        )
+        .into());
+    }
+    settings::set(db, table_name, column_name, FORMAT_KEY, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(format: &str, value: f64) -> String {
+        match DisplayFormat::parse(format) {
+            Some(DisplayFormat::Number(format)) => format.apply(value),
+            other => panic!("{:?} is not a number format", other),
+        }
+    }
+
+    #[test]
+    fn test_number_format() {
+        assert_eq!(number("%.2f", 12.3456), "12.35");
+        assert_eq!(number("%.0f", 2.5), "2");
+        assert_eq!(number("%.2f €", -3.0), "-3.00 €");
+        assert_eq!(number("1,234.50 €", 1234567.891), "1,234,567.89 €");
+        assert_eq!(number("€ 1.234,50", 1234.5), "€ 1.234,50");
+        assert_eq!(number("1,000", 999.0), "999");
+        assert_eq!(number("1,000", 12345.4), "12,345");
+        assert_eq!(number("0.000", 0.5), "0.500");
+        assert_eq!(number("0", -0.2), "0");
+    }
+
+    #[test]
+    fn test_parse_display_format() {
+        assert_eq!(
+            DisplayFormat::parse("%d.%m."),
+            Some(DisplayFormat::Date("%d.%m.".to_string()))
+        );
+        assert_eq!(
+            DisplayFormat::parse("%H:%M"),
+            Some(DisplayFormat::Time("%H:%M".to_string()))
+        );
+        assert_eq!(
+            DisplayFormat::parse("relative"),
+            Some(DisplayFormat::Relative)
+        );
+        assert_eq!(DisplayFormat::parse("bold"), None);
+        assert_eq!(DisplayFormat::parse("%x"), None);
+        assert!(DisplayFormat::parse("%.20f").is_some());
+        assert_eq!(DisplayFormat::parse("%.21f"), None);
+        assert_eq!(DisplayFormat::parse("%.99999999999f"), None);
+        assert_eq!(DisplayFormat::parse(&format!("0.{}", "0".repeat(21))), None);
+    }
+}
@@ -0,0 +1,304 @@
+use std::cmp::Ordering;
+
+use rzdb::{Data, Db};
+use termion::color::{AnsiValue, Bg, Fg, Reset};
+use termion::style;
+
+use crate::column_type::parse_bool;
+use crate::date::{self, Ymd};
+use crate::error::Result;
+use crate::format::is_numeric;
+use crate::sort;
+
+// conditional formatting: a cell, or its whole row, is coloured when a cell passes a test
+// like date < today. the rules are stored in the database next to the tables like the
+// settings, later rules win over earlier ones.
+pub(crate) const HIGHLIGHTS_TABLE: &str = ".highlights";
+const HIGHLIGHTS_COLUMNS: [&str; 5] = ["table_name", "column_name", "operator", "value", "style"];
+
+// the ansi colors in their terminal order
+const COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Contains,
+}
+
+impl Operator {
+    pub fn parse(s: &str) -> Option<Operator> {
+        match s {
+            "=" | "==" => Some(Operator::Equal),
+            "!=" | "<>" => Some(Operator::NotEqual),
+            "<" => Some(Operator::Less),
+            "<=" => Some(Operator::LessOrEqual),
+            ">" => Some(Operator::Greater),
+            ">=" => Some(Operator::GreaterOrEqual),
+            "~" => Some(Operator::Contains),
+            _ => None,
+        }
+    }
+
+    fn accepts(&self, ordering: Ordering) -> bool {
+        match self {
+            Operator::Equal => ordering == Ordering::Equal,
+            Operator::NotEqual => ordering != Ordering::Equal,
+            Operator::Less => ordering == Ordering::Less,
+            Operator::LessOrEqual => ordering != Ordering::Greater,
+            Operator::Greater => ordering == Ordering::Greater,
+            Operator::GreaterOrEqual => ordering != Ordering::Less,
+            Operator::Contains => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    fg: Option<u8>,
+    bg: Option<u8>,
+    bold: bool,
+    dim: bool,
+}
+
+impl Style {
+    // later styles override the colors of earlier ones
+    pub fn merge(&mut self, other: &Style) {
+        self.fg = other.fg.or(self.fg);
+        self.bg = other.bg.or(self.bg);
+        self.bold |= other.bold;
+        self.dim |= other.dim;
+    }
+
+    pub fn start(&self) -> String {
+        let mut s = String::new();
+        if let Some(fg) = self.fg {
+            s += &format!("{}", Fg(AnsiValue(fg)));
+        }
+        if let Some(bg) = self.bg {
+            s += &format!("{}", Bg(AnsiValue(bg)));
+        }
+        if self.bold {
+            s += &format!("{}", style::Bold);
+        }
+        if self.dim {
+            s += &format!("{}", style::Faint);
+        }
+        s
+    }
+
+    pub fn end(&self) -> String {
+        if *self == Style::default() {
+            String::new()
+        } else {
+            format!("{}{}{}", Fg(Reset), Bg(Reset), style::NoFaint)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub x: usize,
+    operator: Operator,
+    value: String,
+    // the value as a date, for date cells
+    date: Option<Ymd>,
+    style: Style,
+    // the style applies to the whole row, not just the cell
+    row: bool,
+}
+
+impl Rule {
+    // the style words are fg=<color>, bg=<color>, bold, dim and row
+    pub fn parse(x: usize, operator: &str, value: &str, style: &str, today: Ymd) -> Option<Rule> {
+        let color = |name: &str| COLORS.iter().position(|c| *c == name).map(|idx| idx as u8);
+        let mut rule = Rule {
+            x,
+            operator: Operator::parse(operator)?,
+            value: value.to_string(),
+            date: date::parse_expression(value, today).or_else(|| Ymd::parse(value)),
+            style: Style::default(),
+            row: false,
+        };
+        for word in style.split_whitespace() {
+            match word.split_once('=') {
+                Some(("fg", name)) => rule.style.fg = Some(color(name)?),
+                Some(("bg", name)) => rule.style.bg = Some(color(name)?),
+                None if word == "bold" => rule.style.bold = true,
+                None if word == "dim" => rule.style.dim = true,
+                None if word == "row" => rule.row = true,
+                _ => return None,
+            }
+        }
+        (rule.style != Style::default()).then_some(rule)
+    }
+
+    // comparisons only hold between cells and values of the same kind,
+    // so empty cells are never before today
+    pub fn matches(&self, data: &Data) -> bool {
+        if self.operator == Operator::Contains {
+            let text = data.to_string().to_lowercase();
+            return text.contains(&self.value.to_lowercase());
+        }
+        let value = match (data, self.date) {
+            (Data::Date(_), Some(date)) => date.to_data(),
+            _ => Data::parse(&self.value),
+        };
+        let is_same_kind = (is_numeric(data) && is_numeric(&value))
+            || std::mem::discriminant(data) == std::mem::discriminant(&value);
+        let ordering = match (parse_bool(&data.to_string()), parse_bool(&self.value)) {
+            (Some(a), Some(b)) if !is_numeric(data) => a.cmp(&b),
+            _ if !is_same_kind => return self.operator == Operator::NotEqual,
+            _ => sort::compare(data, &value),
+        };
+        self.operator.accepts(ordering)
+    }
+}
+
+// style of cell x of a row: the matching rules of its own column and the matching row rules
+pub(crate) fn style(rules: &[Rule], cell: impl Fn(usize) -> Data, x: usize) -> Style {
+    let mut style = Style::default();
+    for rule in rules {
+        if (rule.row || rule.x == x) && rule.matches(&cell(rule.x)) {
+            style.merge(&rule.style);
+        }
+    }
+    style
+}
+
+fn cell(db: &Db, x: usize, y: usize) -> String {
+    db.select_at(HIGHLIGHTS_TABLE, x, y)
+        .map(|data| data.to_string())
+        .unwrap_or_default()
+}
+
+// the rules of a table in order, rules of unknown columns are skipped
+pub(crate) fn get_all(db: &Db, table_name: &str) -> Vec<Rule> {
+    if !db.exists(HIGHLIGHTS_TABLE) {
+        return vec![];
+    }
+    let column_names = db.get_column_names(table_name).unwrap_or_default();
+    let today = Ymd::today();
+    (0..db.get_row_count(HIGHLIGHTS_TABLE).unwrap_or(0))
+        .filter(|y| cell(db, 0, *y) == table_name)
+        .filter_map(|y| {
+            let x = column_names
+                .iter()
+                .position(|column_name| *column_name == cell(db, 1, y))?;
+            Rule::parse(x, &cell(db, 2, y), &cell(db, 3, y), &cell(db, 4, y), today)
+        })
+        .collect()
+}
+
+pub(crate) fn add(
+    db: &mut Db,
+    table_name: &str,
+    column_name: &str,
+    operator: &str,
+    value: &str,
+    style: &str,
+) -> Result<()> {
+    if Operator::parse(operator).is_none() {
+        return Err(format!("Unknown operator {}, use = != < <= > >= or ~", operator).into());
+    }
+    if Rule::parse(0, operator, value, style, Ymd::today()).is_none() {
+        return Err(format!(
+            "Unknown style {}, use fg=<color> bg=<color> bold dim row with the colors {}",
+            style,
+            COLORS.join(" ")
+        )
+        .into());
+    }
+    if !db.exists(HIGHLIGHTS_TABLE) {
+        db.create_table(HIGHLIGHTS_TABLE)?;
+        for column in HIGHLIGHTS_COLUMNS {
+            db.create_column(HIGHLIGHTS_TABLE, column)?;
+        }
+    }
+    let y = db.get_row_count(HIGHLIGHTS_TABLE)?;
+    db.insert(HIGHLIGHTS_TABLE, vec![""; HIGHLIGHTS_COLUMNS.len()])?;
+    for (x, text) in [table_name, column_name, operator, value, style]
+        .iter()
+        .enumerate()
+    {
+        db.set_at(HIGHLIGHTS_TABLE, y, x, Data::String(text.to_string()))?;
+    }
+    Ok(())
+}
+
+fn remove_where(db: &mut Db, matches: impl Fn(&Db, usize) -> bool) -> Result<()> {
+    if !db.exists(HIGHLIGHTS_TABLE) {
+        return Ok(());
+    }
+    for y in (0..db.get_row_count(HIGHLIGHTS_TABLE)?).rev() {
+        if matches(db, y) {
+            db.delete_row_at(HIGHLIGHTS_TABLE, y)?;
+        }
+    }
+    Ok(())
+}
+
+// remove all rules of a column, also when the column is deleted
+pub(crate) fn clear(db: &mut Db, table_name: &str, column_name: &str) -> Result<()> {
+    remove_where(db, |db, y| {
+        cell(db, 0, y) == table_name && cell(db, 1, y) == column_name
+    })
+}
+
+pub(crate) fn remove_table(db: &mut Db, table_name: &str) -> Result<()> {
+    remove_where(db, |db, y| cell(db, 0, y) == table_name)
+}
+
+// rules follow their column when it's renamed
+pub(crate) fn rename_column(
+    db: &mut Db,
+    table_name: &str,
+    old_column_name: &str,
+    new_column_name: &str,
+) -> Result<()> {
+    if !db.exists(HIGHLIGHTS_TABLE) {
+        return Ok(());
+    }
+    for y in 0..db.get_row_count(HIGHLIGHTS_TABLE)? {
+        if cell(db, 0, y) == table_name && cell(db, 1, y) == old_column_name {
+            let name = Data::String(new_column_name.to_string());
+            db.set_at(HIGHLIGHTS_TABLE, y, 1, name)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule() {
+        let today = Ymd::new(2023, 3, 15).unwrap();
+        let rule = |op: &str, value: &str| Rule::parse(0, op, value, "fg=red", today).unwrap();
+        let d = |s: &str| Data::parse(s);
+        assert!(rule("<", "today").matches(&d("2023-03-14")));
+        assert!(!rule("<", "today").matches(&d("2023-03-15")));
+        assert!(!rule("<", "today").matches(&Data::Empty));
+        assert!(rule(">=", "+1w").matches(&d("2023-03-22")));
+        assert!(rule(">", "10").matches(&d("10.5")));
+        assert!(!rule(">", "10").matches(&d("apple")));
+        assert!(rule("=", "yes").matches(&d("true")));
+        assert!(rule("~", "urg").matches(&d("Urgent")));
+        assert!(rule("!=", "done").matches(&Data::Empty));
+        assert_eq!(Rule::parse(0, "=", "x", "fg=pink", today), None);
+        assert_eq!(Rule::parse(0, "=", "x", "row", today), None);
+        let rule = Rule::parse(0, "=", "x", "bg=blue bold row", today).unwrap();
+        assert!(rule.row);
+        assert_eq!(
+            rule.style.start(),
+            format!("{}{}", Bg(AnsiValue(4)), style::Bold)
+        );
+    }
+}
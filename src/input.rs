@@ -1,15 +1,24 @@
 #[allow(unused_imports)]
 use std::io::{stdin, stdout, Write};
-use termion::event::Key;
+use termion::event::{Event, Key, MouseButton, MouseEvent};
 use termion::input::TermRead;
 #[allow(unused_imports)]
 use termion::raw::IntoRawMode;
 
-use crate::command::Command;
+use crate::calendar;
+use crate::command::{Command, Motion, Operator};
 use crate::common;
+use crate::complete;
+use crate::date::Ymd;
 use crate::editor::Editor;
+use crate::fill::FillMode;
+use crate::join;
+use crate::keys::KeyMap;
 use crate::mode::Mode;
+use crate::motion;
 use crate::pos::Pos;
+use crate::registers;
+use crate::render::Layout;
 use crate::State;
 
 fn move_cursor(cursor: &mut Pos, dx: i16, dy: i16) {
@@ -22,160 +31,492 @@ fn move_cursor(cursor: &mut Pos, dx: i16, dy: i16) {
     }
 }
 
+// counts are capped, larger ones only make motions and operators run for ages
+const MAX_COUNT: usize = 99999;
+
+// keys of an unfinished normal mode command: a count, an operator waiting for its motion,
+// the first g of gg, the z of a fold command or a register name
+#[derive(Default)]
+pub(crate) struct Pending {
+    count: usize,
+    operator: Option<(Operator, usize)>,
+    g_prefix: bool,
+    z_prefix: bool,
+    register_prefix: bool,
+    pub register: Option<char>,
+}
+
+// outline commands, e. g. za. the fold commands take no count, so a count typed after
+// the z is dropped instead of carrying over to the next command.
+fn z_command(c: Key, pending: &mut Pending) -> Command {
+    pending.z_prefix = false;
+    pending.count = 0;
+    match c {
+        Key::Char('a') => Command::ToggleFold,
+        Key::Char('M') => Command::FoldAll,
+        Key::Char('R') => Command::UnfoldAll,
+        Key::Char('k') => Command::MoveSubtreeUp,
+        Key::Char('j') => Command::MoveSubtreeDown,
+        Key::Char('<') => Command::IndentSubtreeLeft,
+        Key::Char('>') => Command::IndentSubtreeRight,
+        _ => Command::None,
+    }
+}
+
+// register name after ", e. g. the a in "ayy. a digit names one of the numbered
+// registers here, it isn't a count
+fn register_name(c: Key, pending: &mut Pending) {
+    pending.register_prefix = false;
+    if let Key::Char(ch) = c {
+        if registers::is_register(ch) {
+            pending.register = Some(ch);
+        }
+    }
+}
+
+// the completion highlighted after key c, None if the key isn't for the completions.
+// Tab and Shift-Tab only cycle once Ctrl-n or Ctrl-p opened the list, so that they still
+// leave the cell otherwise.
+fn cycle_completion(c: Key, picked: Option<usize>, count: usize) -> Option<usize> {
+    if count == 0 {
+        return None;
+    }
+    match (c, picked) {
+        (Key::Ctrl('n'), _) | (Key::Char('\t'), Some(_)) => {
+            Some(picked.map_or(0, |idx| (idx + 1) % count))
+        }
+        (Key::Ctrl('p'), _) | (Key::BackTab, Some(_)) => {
+            Some((picked.unwrap_or(0) + count - 1) % count)
+        }
+        _ => None,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn normal(
+    c: Key,
+    db: &rzdb::Db,
+    state: &State,
+    cursor: &mut Pos,
+    selection: &mut Option<Pos>,
+    command: &mut Command,
+    last_command: &mut Command,
+    mode: &mut Mode,
+    pending: &mut Pending,
+    window_height: i16,
+    message: &mut String,
+) {
+    if pending.register_prefix {
+        register_name(c, pending);
+        return;
+    }
+    // count prefix, a leading 0 is still "first column"
+    if let Key::Char(ch @ '0'..='9') = c {
+        if ch != '0' || pending.count > 0 {
+            let digit = ch as usize - '0' as usize;
+            pending.count = (pending.count.min(MAX_COUNT) * 10 + digit).min(MAX_COUNT);
+            return;
+        }
+    }
+    if pending.z_prefix {
+        *command = z_command(c, pending);
+        if command.is_repeatable() {
+            *last_command = *command;
+        }
+        return;
+    }
+    let count = std::mem::take(&mut pending.count);
+    let g_prefix = std::mem::take(&mut pending.g_prefix);
+
+    let motion = match c {
+        Key::Char('h') | Key::Left | Key::BackTab => Some(Motion::Left),
+        Key::Char('l') | Key::Right | Key::Char('\t') => Some(Motion::Right),
+        Key::Char('k') | Key::Up => Some(Motion::Up),
+        Key::Char('j') | Key::Down | Key::Char('\n') => Some(Motion::Down),
+        Key::Char('w') => Some(Motion::WordForward),
+        Key::Char('b') => Some(Motion::WordBackward),
+        Key::Char('}') => Some(Motion::ParagraphForward),
+        Key::Char('{') => Some(Motion::ParagraphBackward),
+        Key::Char('g') if g_prefix && count > 0 => Some(Motion::Row(count)),
+        Key::Char('g') if g_prefix => Some(Motion::FirstRow),
+        Key::Char('G') if count > 0 => Some(Motion::Row(count)),
+        Key::Char('G') => Some(Motion::LastRow),
+        _ => None,
+    };
+
+    // second half of an operator command, e. g. the j in 3dj
+    if let Some((operator, operator_count)) = pending.operator.take() {
+        let motion = match c {
+            Key::Char('g') if !g_prefix => {
+                pending.operator = Some((operator, operator_count));
+                pending.count = count;
+                pending.g_prefix = true;
+                return;
+            }
+            Key::Char(ch) if Operator::from_key(ch) == Some(operator) => Some(Motion::Line),
+            // yl yanks the row, as it did before there were motions
+            Key::Char('l') if operator == Operator::Yank => Some(Motion::Line),
+            Key::Char('c') => Some(Motion::Column),
+            _ => motion,
+        };
+        *mode = Mode::Normal;
+        if let Some(motion) = motion {
+            let count = operator_count.saturating_mul(count.max(1)).min(MAX_COUNT);
+            *command = Command::Operate(operator, motion, count);
+            *last_command = *command;
+        }
+        return;
+    }
+
+    if g_prefix && c == Key::Char('d') {
+        *command = Command::GotoReference;
+        return;
+    }
+    if g_prefix && c == Key::Char('c') {
+        *command = Command::Calendar;
+        return;
+    }
+
+    if let Some(motion) = motion {
+        *cursor = motion::target(db, state, cursor, motion, count);
+        return;
+    }
+
+    match c {
+        Key::Char('q') => *command = Command::Quit,
+        Key::Char('.') => {
+            *command = match *last_command {
+                Command::Operate(operator, motion, _) if count > 0 => {
+                    Command::Operate(operator, motion, count)
+                }
+                last_command => last_command,
+            }
+        }
+        Key::Char(':') => *command = Command::CommandLineEnter,
+        Key::Char('v') => {
+            *selection = match selection {
+                Some(_) => None,
+                None => Some(*cursor),
+            }
+        }
+        Key::Esc => *selection = None,
+        Key::Char('\'') | Key::Ctrl('6') => *command = Command::PreviousFile, // Ctrl-^ can't be mapped in console
+
+        Key::PageUp | Key::Ctrl('b') => move_cursor(cursor, 0, -(window_height - 5)),
+        Key::PageDown | Key::Ctrl('f') => move_cursor(cursor, 0, window_height - 5),
+        Key::Ctrl('u') => move_cursor(cursor, 0, -(window_height - 5) / 2),
+        Key::Ctrl('d') => move_cursor(cursor, 0, (window_height - 5) / 2),
+
+        Key::Char('0') | Key::Home => cursor.x = 1,
+        Key::Char('$') | Key::End => {
+            cursor.x = db.get_column_count(&state.table_name).unwrap_or(0).max(1)
+        }
+        Key::Char('g') => {
+            pending.count = count;
+            pending.g_prefix = true;
+        }
+        Key::Char('z') => pending.z_prefix = true,
+        Key::Char('"') => {
+            pending.count = count;
+            pending.register_prefix = true;
+        }
+
+        // operators wait for their motion
+        Key::Char(ch @ ('d' | 'y' | 'c')) => {
+            let operator = Operator::from_key(ch).unwrap();
+            pending.operator = Some((operator, count.max(1)));
+            *mode = match operator {
+                Operator::Delete => Mode::Delete,
+                Operator::Yank => Mode::Yank,
+                _ => Mode::Change,
+            };
+        }
+        // indenting works on count rows right away
+        Key::Char('<') => *command = Command::Operate(Operator::IndentLeft, Motion::Line, count),
+        Key::Char('>') => *command = Command::Operate(Operator::IndentRight, Motion::Line, count),
+        Key::Char('x') | Key::Delete => {
+            *command = Command::Operate(Operator::Delete, Motion::Right, count)
+        }
+        Key::Char('Y') => *command = Command::Operate(Operator::Yank, Motion::Line, count),
+        Key::Char('F') => *command = Command::Fill(FillMode::Series),
+        Key::Alt('h') => *command = Command::Move(Motion::Left, count),
+        Key::Alt('l') => *command = Command::Move(Motion::Right, count),
+        Key::Alt('k') => *command = Command::Move(Motion::Up, count),
+        Key::Alt('j') => *command = Command::Move(Motion::Down, count),
+
+        Key::Char(',') => *command = Command::PasteToday,
+        Key::Char(';') => *command = Command::PasteNow,
+        Key::Ctrl('a') => *command = Command::Increment(count.max(1) as isize),
+        Key::Ctrl('x') => *command = Command::Increment(-(count.max(1) as isize)),
+        Key::Char(' ') => *command = Command::ToggleCheckbox,
+        Key::Char('I') => *command = Command::InsertEmptyColumn,
+        Key::Char('O') => *command = Command::InsertEmptyRowAbove,
+        Key::Char('o') => *command = Command::InsertEmptyRowBelow,
+
+        Key::Char('i') => *command = Command::InsertStart,
+        Key::Char('a') | Key::Char('A') | Key::F(2) => *command = Command::InsertEnd,
+        Key::Char('C') => *command = Command::ChangeCell,
+        Key::Ctrl('e') => *command = Command::ExternalEdit,
+
+        Key::Ctrl('c') => *command = Command::Operate(Operator::Yank, Motion::Right, 1),
+        Key::Ctrl('v') => *command = Command::PasteReplace,
+        Key::Char('p') => *command = Command::PasteAfter,
+        Key::Char('P') => *command = Command::PasteBefore,
+
+        // Key::Backspace => println!("×"),
+        // Key::Esc => println!("ESC"),
+        // Key::Char(c) => println!("{}", c),
+        // Key::Alt(c) => println!("^{}", c),
+        _ => common::set_error_message(&format!("Unknown key {:?}", c), message, mode),
+    }
+    if command.is_repeatable() {
+        *last_command = *command;
+    }
+}
+
+fn mouse(
+    mouse_event: MouseEvent,
+    layout: &Layout,
+    cursor: &mut Pos,
+    selection: &mut Option<Pos>,
+    mode: &Mode,
+) {
+    let list_mode = matches!(
+        mode,
+        Mode::ListReadOnly | Mode::ListTables | Mode::ListDatabases
+    );
+    if *mode != Mode::Normal && !list_mode {
+        return;
+    }
+    let move_to = |cursor: &mut Pos, x: u16, y: u16| {
+        let Some(pos) = layout.cell_at(x, y) else {
+            return;
+        };
+        if !list_mode && x as usize >= layout.margin_left {
+            cursor.x = pos.x;
+        }
+        cursor.y = if list_mode { pos.y.max(1) } else { pos.y };
+    };
+    match mouse_event {
+        MouseEvent::Press(MouseButton::Left, x, y) => {
+            move_to(cursor, x, y);
+            *selection = if list_mode { None } else { Some(*cursor) };
+        }
+        MouseEvent::Hold(x, y) => {
+            if selection.is_some() {
+                move_to(cursor, x, y);
+            }
+        }
+        MouseEvent::Release(_, _) => {
+            // a click without dragging doesn't leave a selection behind
+            if *selection == Some(*cursor) {
+                *selection = None;
+            }
+        }
+        MouseEvent::Press(MouseButton::WheelUp, _, _) => move_cursor(cursor, 0, -3),
+        MouseEvent::Press(MouseButton::WheelDown, _, _) => move_cursor(cursor, 0, 3),
+        MouseEvent::Press(_, _, _) => {}
+    }
+}
+
+// termion doesn't know Shift-Enter, terminals with extended key reporting send one of these
+fn is_shift_enter(bytes: &[u8]) -> bool {
+    matches!(bytes, b"\x1b[13;2u" | b"\x1b[27;2;13~")
+}
+
+// keys of the cell editor and the command line
+fn editor_key(
+    c: Key,
+    mode: &mut Mode,
+    command: &mut Command,
+    editor: &mut Editor,
+    key_map: &KeyMap,
+    message: &mut String,
+) {
+    match c {
+        _ if c == key_map.transpose => editor.transpose(),
+        // multi-line cells, Up/Down leave the editor from the first/last line only
+        Key::Alt('\r' | '\n') if *mode == Mode::Insert => editor.newline(),
+        Key::Up if *mode == Mode::Insert && editor.row_col().0 > 0 => editor.up(),
+        Key::Down if *mode == Mode::Insert && editor.row_col().0 + 1 < editor.line_count() => {
+            editor.down()
+        }
+        Key::Esc | Key::Char('\t') | Key::Char('\n') | Key::BackTab | Key::Up | Key::Down => {
+            match mode {
+                Mode::Insert => {
+                    *command = match c {
+                        Key::Esc => Command::EditorExit,
+                        Key::Char('\t') => Command::EditorExitRight,
+                        Key::BackTab => Command::EditorExitLeft,
+                        Key::Up => Command::EditorExitUp,
+                        Key::Down => Command::EditorExitDown,
+                        Key::Char('\n') => Command::EditorEnter,
+                        _ => Command::None,
+                    };
+                }
+                Mode::Command => {
+                    *command = if c == Key::Char('\n') {
+                        Command::CommandLineExit
+                    } else {
+                        Command::None
+                    };
+                }
+                _ => common::set_error_message(
+                    &format!("Mode {:?} should not appear here", mode),
+                    message,
+                    mode,
+                ),
+            }
+            if *mode != Mode::Error {
+                *mode = Mode::Normal;
+            }
+        }
+        Key::Ctrl('v') => {
+            // the cell editor stays open and shows the message below it
+            match editor.insert_clipboard() {
+                Err(e) if *mode == Mode::Insert => *message = e.to_string(),
+                Err(e) => common::set_error_message(&e.to_string(), message, mode),
+                Ok(()) => {}
+            }
+        }
+        Key::Ctrl('a') | Key::Home => editor.home(),
+        Key::Ctrl('e') | Key::End => editor.end(),
+        Key::Ctrl('u') => editor.delete_left_all(),
+        Key::Ctrl('k') => editor.delete_right_all(),
+        Key::Ctrl('w') => editor.delete_word(),
+        Key::Ctrl('y') => editor.yank(),
+        Key::Alt('y') => editor.yank_pop(),
+        Key::Ctrl('z') | Key::Ctrl('7') => editor.undo(), // Ctrl-7 is how termion reports Ctrl-_
+        Key::Ctrl('r') => editor.redo(),
+        Key::Left | Key::Ctrl('b') => editor.left(),
+        Key::Right | Key::Ctrl('f') => editor.right(),
+        Key::Ctrl('d') => editor.indent_left(),
+        Key::Ctrl('t') => editor.indent_right(),
+        Key::Ctrl('g') | Key::Alt('b') => editor.word_left(),
+        Key::Ctrl('l') => editor.word_right(),
+        Key::Alt('f') => editor.word_forward(),
+        Key::Alt('d') => editor.delete_word_forward(),
+        Key::Char(c) => editor.add(c),
+        Key::Ctrl('h') | Key::Backspace => editor.backspace(),
+        Key::Delete => editor.delete(),
+        _ => {}
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn input(
     db: &rzdb::Db,
     state: &State,
+    layout: &Layout,
     cursor: &mut Pos,
+    selection: &mut Option<Pos>,
     command: &mut Command,
     last_command: &mut Command,
+    pending: &mut Pending,
     mode: &mut Mode,
     editor: &mut Editor,
+    key_map: &KeyMap,
     message: &mut String,
 ) {
     let stdin = stdin();
     let mut stdout = stdout().into_raw_mode().unwrap();
     let window_height = termion::terminal_size().unwrap().1 as i16;
-    *last_command = *command;
     *command = Command::None;
     //let c = stdin.keys().next().unwrap();
-    if let Some(c) = stdin.keys().next() {
-        let c = c.unwrap();
+    let c = match stdin.events().next() {
+        Some(Ok(Event::Key(c))) => Some(c),
+        Some(Ok(Event::Unsupported(bytes))) if is_shift_enter(&bytes) => {
+            if *mode == Mode::Insert {
+                *command = Command::EditorShiftEnter;
+                *mode = Mode::Normal;
+            }
+            None
+        }
+        Some(Ok(Event::Mouse(mouse_event))) => {
+            mouse(mouse_event, layout, cursor, selection, mode);
+            None
+        }
+        _ => None,
+    };
+    if let Some(c) = c {
         match mode.clone() {
-            Mode::Normal => match c {
-                Key::Char('q') => *command = Command::Quit,
-                Key::Char('.') => *command = *last_command,
-                Key::Char(':') => *command = Command::CommandLineEnter,
-                Key::Char('\'') | Key::Ctrl('6') => *command = Command::PreviousFile, // Ctrl-^ can't be mapped in console
+            Mode::Normal | Mode::Yank | Mode::Delete | Mode::Change => normal(
+                c,
+                db,
+                state,
+                cursor,
+                selection,
+                command,
+                last_command,
+                mode,
+                pending,
+                window_height,
+                message,
+            ),
 
-                Key::Char('j') => move_cursor(cursor, 0, 1),
-                Key::Char('k') => move_cursor(cursor, 0, -1),
-                Key::Char('h') => move_cursor(cursor, -1, 0),
-                Key::Char('l') => move_cursor(cursor, 1, 0),
-                Key::Left => move_cursor(cursor, -1, 0),
-                Key::Right => move_cursor(cursor, 1, 0),
-                Key::Up => move_cursor(cursor, 0, -1),
-                Key::Down => move_cursor(cursor, 0, 1),
-                Key::Char('\t') => move_cursor(cursor, 1, 0),
-                Key::BackTab => move_cursor(cursor, -1, 0),
-                Key::Char('\n') => move_cursor(cursor, 0, 1),
-                Key::PageUp | Key::Ctrl('b') => move_cursor(cursor, 0, -(window_height - 5)),
-                Key::PageDown | Key::Ctrl('f') => move_cursor(cursor, 0, window_height - 5),
-                Key::Ctrl('u') => move_cursor(cursor, 0, -(window_height - 5) / 2),
-                Key::Ctrl('d') => move_cursor(cursor, 0, (window_height - 5) / 2),
-
-                Key::Char('0') | Key::Home => cursor.x = 1,
-                Key::Char('$') | Key::End => {
-                    cursor.x = db.get_column_names(&state.table_name).unwrap().len()
-                }
-                Key::Char('g') => cursor.y = 1,
-                Key::Char('G') => cursor.y = db.select_from(&state.table_name).unwrap().len(),
-
-                Key::Char('<') => *command = Command::IndentLeft,
-                Key::Char('>') => *command = Command::IndentRight,
-
-                Key::Char(',') => *command = Command::PasteToday,
-                Key::Char('I') => *command = Command::InsertEmptyColumn,
-                Key::Char('O') => *command = Command::InsertEmptyRowAbove,
-                Key::Char('o') => *command = Command::InsertEmptyRowBelow,
-
-                Key::Char('i') => *command = Command::InsertStart,
-                Key::Char('a') | Key::Char('A') | Key::F(2) => *command = Command::InsertEnd,
-                Key::Char('x') => *command = Command::DeleteCell,
-                Key::Delete => *command = Command::DeleteCell,
-                Key::Char('d') => *mode = Mode::Delete,
-                Key::Char('C') => *command = Command::ChangeCell,
-
-                Key::Ctrl('c') => *command = Command::YankCell,
-                Key::Ctrl('v') => *command = Command::PasteReplace,
-                Key::Char('y') => *mode = Mode::Yank,
-                Key::Char('p') => *command = Command::PasteAfter,
-                Key::Char('Y') => *command = Command::YankRow,
-                Key::Char('P') => *command = Command::PasteBefore,
-
-                // Key::Backspace => println!("×"),
-                // Key::Esc => println!("ESC"),
-                // Key::Char(c) => println!("{}", c),
-                // Key::Alt(c) => println!("^{}", c),
-                _ => common::set_error_message(&format!("Unknown key {:?}", c), message, mode),
-            },
-
-            Mode::Insert | Mode::Command => match c {
-                Key::Esc
-                | Key::Char('\t')
-                | Key::Char('\n')
-                | Key::BackTab
-                | Key::Up
-                | Key::Down => {
-                    match mode {
-                        Mode::Insert => {
-                            *command = match c {
-                                Key::Esc => Command::EditorExit,
-                                Key::Char('\t') => Command::EditorExitRight,
-                                Key::BackTab => Command::EditorExitLeft,
-                                Key::Up => Command::EditorExitUp,
-                                Key::Down => Command::EditorExitDown,
-                                Key::Char('\n') => Command::EditorNewLine,
-                                _ => Command::None,
-                            };
-                        }
-                        Mode::Command => {
-                            *command = if c == Key::Char('\n') {
-                                Command::CommandLineExit
-                            } else {
-                                Command::None
-                            };
+            Mode::Insert | Mode::Command => {
+                // the join picker takes the highlighted key when leaving the editor
+                let choices = match mode {
+                    Mode::Insert => join::picker(db, state, cursor, &editor.line),
+                    _ => vec![],
+                };
+                // completions from the column are only taken with Enter
+                let completions = match mode {
+                    Mode::Insert if choices.is_empty() => {
+                        complete::candidates(db, state, cursor, &editor.line)
+                    }
+                    _ => vec![],
+                };
+                let picked = editor.highlighted();
+                let cycled = cycle_completion(c, editor.picked, completions.len());
+                match c {
+                    Key::Ctrl('n') if !choices.is_empty() => {
+                        editor.picked = Some(picked.map_or(0, |idx| (idx + 1) % choices.len()));
+                    }
+                    Key::Ctrl('p') if !choices.is_empty() => {
+                        let idx = picked.unwrap_or(0) + choices.len() - 1;
+                        editor.picked = Some(idx % choices.len());
+                    }
+                    _ if cycled.is_some() => editor.picked = cycled,
+                    Key::Char('\n') if !completions.is_empty() && editor.picked.is_some() => {
+                        if let Some(value) = editor.picked.and_then(|idx| completions.get(idx)) {
+                            editor.replace(value);
                         }
-                        _ => common::set_error_message(
-                            &format!("Mode {:?} should not appear here", mode),
-                            message,
-                            mode,
-                        ),
+                        editor.picked = None;
                     }
-                    if *mode != Mode::Error {
-                        *mode = Mode::Normal;
+                    _ => {
+                        if let (Key::Char('\t' | '\n') | Key::BackTab, Some(idx)) = (c, picked) {
+                            if let Some((key, _)) = choices.get(idx) {
+                                editor.insert_at(key, key.chars().count());
+                            }
+                        }
+                        editor.picked = None;
+                        editor_key(c, mode, command, editor, key_map, message);
                     }
                 }
-                Key::Ctrl('v') => editor.insert_clipboard(),
-                Key::Ctrl('a') | Key::Home => editor.home(),
-                Key::Ctrl('e') | Key::End => editor.end(),
-                Key::Ctrl('u') => editor.delete_left_all(),
-                Key::Ctrl('k') => editor.delete_right_all(),
-                Key::Ctrl('w') => editor.delete_word(),
-                Key::Left | Key::Ctrl('b') => editor.left(),
-                Key::Right | Key::Ctrl('f') => editor.right(),
-                Key::Ctrl('d') => editor.indent_left(),
-                Key::Ctrl('t') => editor.indent_right(),
-                Key::Ctrl('g') => editor.word_left(),
-                Key::Ctrl('l') => editor.word_right(),
-                Key::Char(c) => editor.add(c),
-                Key::Ctrl('h') | Key::Backspace => editor.backspace(),
-                Key::Delete => editor.delete(),
-                _ => {}
-            },
+            }
 
-            Mode::Yank => match c {
-                Key::Esc => *mode = Mode::Normal,
-                Key::Char('l') | Key::Char('y') => {
+            Mode::Calendar => match c {
+                Key::Char('h') | Key::Left => calendar::shift(editor, -1, 0),
+                Key::Char('l') | Key::Right => calendar::shift(editor, 1, 0),
+                Key::Char('k') | Key::Up => calendar::shift(editor, -7, 0),
+                Key::Char('j') | Key::Down => calendar::shift(editor, 7, 0),
+                Key::Char('H') | Key::PageUp => calendar::shift(editor, 0, -1),
+                Key::Char('L') | Key::PageDown => calendar::shift(editor, 0, 1),
+                Key::Char('t') => calendar::set(editor, Ymd::today()),
+                // type the date instead, e. g. +3d
+                Key::Char('i') => *mode = Mode::Insert,
+                Key::Char('\n') => {
+                    *command = Command::EditorExit;
                     *mode = Mode::Normal;
-                    *command = Command::YankRow;
                 }
-                Key::Char('c') => {
+                Key::Esc | Key::Char('q') => {
+                    editor.clear();
                     *mode = Mode::Normal;
-                    *command = Command::YankColumn;
                 }
                 _ => {}
             },
 
-            Mode::Delete => {
-                match c {
-                    Key::Char('d') => *command = Command::DeleteLine,
-                    Key::Char('c') => *command = Command::DeleteColumn,
-                    _ => {}
-                }
-                *mode = Mode::Normal;
-            }
-
             Mode::ListReadOnly | Mode::ListTables | Mode::ListDatabases => match c {
                 Key::Char('j') => move_cursor(cursor, 0, 1),
                 Key::Char('k') => move_cursor(cursor, 0, -1),
@@ -186,7 +527,7 @@ pub(crate) fn input(
                 Key::Ctrl('u') => move_cursor(cursor, 0, -(window_height - 5) / 2),
                 Key::Ctrl('d') => move_cursor(cursor, 0, (window_height - 5) / 2),
                 Key::Char('g') => cursor.y = 1,
-                Key::Char('G') => cursor.y = db.select_from(&state.table_name).unwrap().len(),
+                Key::Char('G') => cursor.y = db.get_row_count(&state.table_name).unwrap_or(0),
 
                 Key::Char('\n') => match *mode {
                     Mode::ListTables => *command = Command::ListTablesEnter,
@@ -206,3 +547,49 @@ pub(crate) fn input(
 
     write!(stdout, "{}", termion::cursor::Show).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_z_command() {
+        // z5j: the 5 is counted while the z waits, it must not reach the next command
+        let mut pending = Pending {
+            z_prefix: true,
+            count: 5,
+            ..Default::default()
+        };
+        assert!(z_command(Key::Char('j'), &mut pending) == Command::MoveSubtreeDown);
+        assert_eq!(pending.count, 0);
+        assert!(!pending.z_prefix);
+        pending.z_prefix = true;
+        assert!(z_command(Key::Char('x'), &mut pending) == Command::None);
+    }
+
+    #[test]
+    fn test_cycle_completion() {
+        // Tab leaves the cell until the completions are cycled with Ctrl-n
+        assert_eq!(cycle_completion(Key::Char('\t'), None, 3), None);
+        assert_eq!(cycle_completion(Key::BackTab, None, 3), None);
+        assert_eq!(cycle_completion(Key::Ctrl('n'), None, 3), Some(0));
+        assert_eq!(cycle_completion(Key::Char('\t'), Some(0), 3), Some(1));
+        assert_eq!(cycle_completion(Key::Char('\t'), Some(2), 3), Some(0));
+        assert_eq!(cycle_completion(Key::BackTab, Some(0), 3), Some(2));
+        assert_eq!(cycle_completion(Key::Ctrl('p'), None, 3), Some(2));
+        assert_eq!(cycle_completion(Key::Ctrl('n'), None, 0), None);
+    }
+
+    #[test]
+    fn test_register_name() {
+        // "1p: the 1 names the first numbered register, it's not a count
+        let mut pending = Pending {
+            register_prefix: true,
+            ..Default::default()
+        };
+        register_name(Key::Char('1'), &mut pending);
+        assert_eq!(pending.register, Some('1'));
+        assert_eq!(pending.count, 0);
+        assert!(!pending.register_prefix);
+    }
+}
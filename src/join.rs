@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use rzdb::Db;
+
+use crate::error::Result;
+use crate::pos::Pos;
+use crate::settings;
+use crate::State;
+
+const JOIN_KEY: &str = "join";
+
+// a column whose cells hold keys of rows in another table.
+// the cells show the display column of the referenced row instead of the key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Join {
+    pub table_name: String,
+    pub key_column: String,
+    pub display_column: String,
+}
+
+impl Join {
+    // table:key_column or table:key_column:display_column
+    pub fn parse(s: &str) -> Option<Join> {
+        let parts: Vec<&str> = s.split(':').collect();
+        if parts.iter().any(|part| part.is_empty()) {
+            return None;
+        }
+        match parts.as_slice() {
+            [table_name, key_column] => Some(Join {
+                table_name: table_name.to_string(),
+                key_column: key_column.to_string(),
+                display_column: key_column.to_string(),
+            }),
+            [table_name, key_column, display_column] => Some(Join {
+                table_name: table_name.to_string(),
+                key_column: key_column.to_string(),
+                display_column: display_column.to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn to_setting(&self) -> String {
+        if self.display_column == self.key_column {
+            format!("{}:{}", self.table_name, self.key_column)
+        } else {
+            format!(
+                "{}:{}:{}",
+                self.table_name, self.key_column, self.display_column
+            )
+        }
+    }
+
+    fn column_x(&self, db: &Db, column_name: &str) -> Result<usize> {
+        db.get_column_names(&self.table_name)?
+            .iter()
+            .position(|name| name == column_name)
+            .ok_or_else(|| {
+                format!(
+                    "Column {} does not exist in {}",
+                    column_name, self.table_name
+                )
+                .into()
+            })
+    }
+
+    pub fn key_x(&self, db: &Db) -> Result<usize> {
+        self.column_x(db, &self.key_column)
+    }
+
+    // (key, display value) of every row of the referenced table
+    pub fn rows(&self, db: &Db) -> Vec<(String, String)> {
+        let (key_x, display_x) = match (self.key_x(db), self.column_x(db, &self.display_column)) {
+            (Ok(key_x), Ok(display_x)) => (key_x, display_x),
+            _ => return vec![],
+        };
+        let text = |x: usize, y: usize| {
+            db.select_at(&self.table_name, x, y)
+                .map(|data| data.to_string())
+                .unwrap_or_default()
+        };
+        (0..db.get_row_count(&self.table_name).unwrap_or(0))
+            .map(|y| (text(key_x, y), text(display_x, y)))
+            .filter(|(key, _)| !key.is_empty())
+            .collect()
+    }
+
+    pub fn display_values(&self, db: &Db) -> HashMap<String, String> {
+        self.rows(db).into_iter().collect()
+    }
+
+    pub fn find_row(&self, db: &Db, key: &str) -> Option<usize> {
+        let key_x = self.key_x(db).ok()?;
+        (0..db.get_row_count(&self.table_name).unwrap_or(0)).find(|&y| {
+            db.select_at(&self.table_name, key_x, y)
+                .is_ok_and(|data| data.to_string() == key)
+        })
+    }
+}
+
+// rows matching the text typed into the editor, exact matches first, then prefixes
+pub(crate) fn choices(rows: &[(String, String)], filter: &str) -> Vec<(String, String)> {
+    let filter = filter.trim().to_lowercase();
+    let rank = |(key, display): &(String, String)| {
+        let (key, display) = (key.to_lowercase(), display.to_lowercase());
+        if key == filter || display == filter {
+            Some(0)
+        } else if key.starts_with(&filter) || display.starts_with(&filter) {
+            Some(1)
+        } else if key.contains(&filter) || display.contains(&filter) {
+            Some(2)
+        } else {
+            None
+        }
+    };
+    let mut ranked: Vec<(usize, &(String, String))> = rows
+        .iter()
+        .filter_map(|row| rank(row).map(|rank| (rank, row)))
+        .collect();
+    ranked.sort_by_key(|(rank, _)| *rank);
+    ranked.into_iter().map(|(_, row)| row.clone()).collect()
+}
+
+// choices of the picker while editing a joined cell, empty for other cells
+pub(crate) fn picker(db: &Db, state: &State, cursor: &Pos, filter: &str) -> Vec<(String, String)> {
+    if cursor.y == 0 {
+        return vec![];
+    }
+    match db
+        .get_column_name_at(&state.table_name, cursor.x - 1)
+        .ok()
+        .and_then(|column_name| get(db, &state.table_name, &column_name))
+    {
+        Some(join) => choices(&join.rows(db), filter),
+        None => vec![],
+    }
+}
+
+pub(crate) fn get(db: &Db, table_name: &str, column_name: &str) -> Option<Join> {
+    settings::get(db, table_name, column_name, JOIN_KEY).and_then(|s| Join::parse(&s))
+}
+
+// join of each column, in column order
+pub(crate) fn get_all(db: &Db, table_name: &str) -> Vec<Option<Join>> {
+    settings::get_for_columns(db, table_name, JOIN_KEY)
+        .iter()
+        .map(|s| s.as_ref().and_then(|s| Join::parse(s)))
+        .collect()
+}
+
+// None removes the join
+pub(crate) fn set(
+    db: &mut Db,
+    table_name: &str,
+    column_name: &str,
+    join: Option<Join>,
+) -> Result<()> {
+    let value = match join {
+        Some(join) => {
+            if !db.exists(&join.table_name) {
+                return Err(format!("Table {} does not exist", join.table_name).into());
+            }
+            join.key_x(db)?;
+            join.column_x(db, &join.display_column)?;
+            join.to_setting()
+        }
+        None => "".to_string(),
+    };
+    settings::set(db, table_name, column_name, JOIN_KEY, &value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(v: &[(&str, &str)]) -> Vec<(String, String)> {
+        v.iter()
+            .map(|(key, display)| (key.to_string(), display.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_join() {
+        let join = Join::parse("people:id:name").unwrap();
+        assert_eq!(join.display_column, "name");
+        assert_eq!(Join::parse(&join.to_setting()), Some(join));
+        assert_eq!(Join::parse("people:id").unwrap().display_column, "id");
+        assert_eq!(Join::parse("people"), None);
+        assert_eq!(Join::parse("people::name"), None);
+    }
+
+    #[test]
+    fn test_choices() {
+        let r = rows(&[("13", "Anna"), ("3", "Bob"), ("30", "Annabel")]);
+        assert_eq!(
+            choices(&r, "3"),
+            rows(&[("3", "Bob"), ("30", "Annabel"), ("13", "Anna")])
+        );
+        assert_eq!(
+            choices(&r, "ann"),
+            rows(&[("13", "Anna"), ("30", "Annabel")])
+        );
+        assert_eq!(choices(&r, "").len(), 3);
+    }
+}
@@ -0,0 +1,84 @@
+use rzdb::Db;
+use termion::event::Key;
+
+use crate::error::Result;
+use crate::meta;
+
+const TRANSPOSE: &str = "transpose";
+
+// editor keys that can be changed with :map, kept in the meta database
+pub(crate) struct KeyMap {
+    pub transpose: Key,
+}
+
+impl KeyMap {
+    pub fn load(meta_db: &Db) -> KeyMap {
+        let key = |action: &str, default: Key| {
+            meta::get_key(meta_db, action)
+                .and_then(|name| parse(&name))
+                .unwrap_or(default)
+        };
+        KeyMap {
+            // Ctrl-t indents
+            transpose: key(TRANSPOSE, Key::Alt('t')),
+        }
+    }
+}
+
+// ctrl-t, alt-t or f5
+pub(crate) fn parse(name: &str) -> Option<Key> {
+    let name = name.to_lowercase();
+    let single = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Some(ch),
+            _ => None,
+        }
+    };
+    if let Some(rest) = name.strip_prefix("ctrl-") {
+        single(rest)
+            .filter(|ch| ch.is_ascii_alphanumeric())
+            .map(Key::Ctrl)
+    } else if let Some(rest) = name.strip_prefix("alt-") {
+        single(rest).map(Key::Alt)
+    } else if let Some(rest) = name.strip_prefix('f') {
+        rest.parse()
+            .ok()
+            .filter(|n| (1..=12).contains(n))
+            .map(Key::F)
+    } else {
+        None
+    }
+}
+
+// :map transpose alt-t
+pub(crate) fn map(
+    mut args: std::str::SplitWhitespace,
+    meta_db: &mut Db,
+    key_map: &mut KeyMap,
+) -> Result<()> {
+    let usage = "usage: map transpose <key>, e. g. ctrl-t, alt-t or f5";
+    let (Some(action), Some(name)) = (args.next(), args.next()) else {
+        return Err(usage.into());
+    };
+    let key = parse(name).ok_or(usage)?;
+    match action {
+        TRANSPOSE => key_map.transpose = key,
+        _ => return Err(format!("Unknown action {}, {}", action, usage).into()),
+    }
+    meta::set_key(meta_db, action, &name.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("Ctrl-T"), Some(Key::Ctrl('t')));
+        assert_eq!(parse("alt-t"), Some(Key::Alt('t')));
+        assert_eq!(parse("f5"), Some(Key::F(5)));
+        assert_eq!(parse("ctrl-"), None);
+        assert_eq!(parse("t"), None);
+    }
+}
@@ -1,18 +1,42 @@
 use arboard::Clipboard;
-use inotify::{Inotify, WatchMask};
+use inotify::{Inotify, WatchDescriptor, WatchMask};
+use termion::input::MouseTerminal;
 
-use rzdb::{time::Date, Data, Db};
+use rzdb::Db;
 
 use common::*;
 
+mod aggregate;
+mod calendar;
+mod checkbox;
+mod column_type;
 mod command;
 mod common;
+mod complete;
+mod date;
 mod editor;
+mod enter;
+mod error;
+mod external;
+mod fill;
+mod format;
+mod highlight;
 mod input;
+mod join;
+mod keys;
 mod meta;
 mod mode;
+mod motion;
+mod notes;
+mod outline;
 mod pos;
+mod registers;
 mod render;
+mod rowid;
+mod settings;
+mod sort;
+mod text;
+mod tsv;
 
 use command::Command;
 use input::input;
@@ -90,7 +114,9 @@ fn main() {
         }
     };
 
-    meta::insert_recent_table(&mut meta_db, &state).unwrap();
+    if let Err(e) = meta::insert_recent_table(&mut meta_db, &state) {
+        set_error_message(&e.to_string(), &mut status_line_message, &mut mode);
+    }
 
     // If the database doesn't exist, create it
     // If there was an error, e. g. parsing, exit
@@ -113,30 +139,31 @@ fn main() {
     };
 
     if !db.exists(&state.table_name) {
-        db.create_table(&state.table_name).unwrap();
-        db.create_column(&state.table_name, "date").unwrap();
-        db.create_column(&state.table_name, "topic").unwrap();
+        if let Err(e) = create_default_table(&mut db, &state.table_name) {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        }
     };
 
     // setup inotify for db_dir (reload database on change)
-    let mut watch_descriptor = inotify
-        .add_watch(
-            &format!("{}/{}", db.get_db_path(), state.db_name),
-            WatchMask::MODIFY | WatchMask::CREATE | WatchMask::DELETE,
-        )
-        .unwrap();
+    let mut watch_descriptor = match add_watch(&mut inotify, &db, &state) {
+        Ok(watch_descriptor) => Some(watch_descriptor),
+        Err(e) => {
+            set_error_message(&e.to_string(), &mut status_line_message, &mut mode);
+            None
+        }
+    };
 
-    // macro to remove the watch descriptor and renew it
+    // macro to remove the watch descriptor and renew it, without a watch the
+    // database just isn't reloaded on changes
     macro_rules! renew_watch_descriptor {
-        () => {
-            inotify.rm_watch(watch_descriptor).unwrap();
-            watch_descriptor = inotify
-                .add_watch(
-                    &format!("{}/{}", db.get_db_path(), state.db_name),
-                    WatchMask::MODIFY | WatchMask::CREATE | WatchMask::DELETE,
-                )
-                .unwrap();
-        };
+        () => {{
+            if let Some(old_watch_descriptor) = watch_descriptor.take() {
+                let _ = inotify.rm_watch(old_watch_descriptor);
+            }
+            add_watch(&mut inotify, &db, &state)
+                .map(|new_watch_descriptor| watch_descriptor = Some(new_watch_descriptor))
+        }};
     }
 
     // copy & paste
@@ -145,27 +172,49 @@ fn main() {
     let mut cursor = pos::Pos::new(1, 1);
     let mut command = Command::new();
     let mut last_command = Command::new();
+    let mut pending = input::Pending::default();
     let mut editor = editor::Editor::new();
+    let mut selection: Option<pos::Pos> = None;
+    let mut folds = outline::Folds::default();
+    let mut key_map = keys::KeyMap::load(&meta_db);
+    // report mouse events as long as rspread is running
+    let mut mouse_terminal = Some(MouseTerminal::from(std::io::stdout()));
     loop {
         // render screen
-        render::render(&db, &state, &cursor, &mode, &editor, &status_line_message);
+        let layout = render::render(
+            &db,
+            &state,
+            &cursor,
+            &selection,
+            &folds,
+            &mode,
+            &editor,
+            &status_line_message,
+        );
 
-        // reset error message display
+        // reset error message display, the message stays on screen until the next render
         if mode == Mode::Error {
             mode = Mode::Normal;
         }
+        status_line_message.clear();
 
         // get user input
+        let previous_y = cursor.y;
         input(
             &db,
             &state,
+            &layout,
             &mut cursor,
+            &mut selection,
             &mut command,
             &mut last_command,
+            &mut pending,
             &mut mode,
             &mut editor,
+            &key_map,
             &mut status_line_message,
         );
+        outline::skip_hidden(&db, &state.table_name, &folds, &mut cursor, previous_y);
 
         // reload database if files in it have changed
         let mut buffer = [0u8; 1024];
@@ -180,108 +229,87 @@ fn main() {
             }
         }
 
-        match command {
+        // a register given with " is used by the next command
+        let register = if command == Command::None {
+            None
+        } else {
+            pending.register.take()
+        };
+
+        let result = match command {
             Command::Quit => break,
-            Command::None => {}
+            Command::None => Ok(()),
             Command::PreviousFile => {
                 if previous_table_name != state.table_name {
                     let tmp = state.table_name;
                     state.table_name = previous_table_name.clone();
                     previous_table_name = tmp;
                     cursor = pos::Pos::new(1, 1);
-                    renew_watch_descriptor!();
+                    renew_watch_descriptor!()
+                } else {
+                    Ok(())
                 }
             }
+            Command::GotoReference => {
+                goto_reference(&db, &mut state, &mut previous_table_name, &mut cursor)
+                    .and(renew_watch_descriptor!())
+            }
+            Command::Calendar => calendar::open(&db, &state, &cursor, &mut editor, &mut mode),
+            Command::ExternalEdit => {
+                external::edit(&mut db, &state, &cursor, &selection, &mut mouse_terminal)
+            }
+            Command::InsertStart | Command::InsertEnd
+                if calendar::is_date_column(&db, &state, &cursor) =>
+            {
+                calendar::open(&db, &state, &cursor, &mut editor, &mut mode)
+            }
             Command::InsertStart => {
                 mode = Mode::Insert;
-                common::editor_enter(&db, &state, &cursor, &mut editor, 0);
+                common::editor_enter(&db, &state, &cursor, &mut editor, 0)
             }
             Command::InsertEnd => {
                 mode = Mode::Insert;
-                editor_enter(&db, &state, &cursor, &mut editor, -1);
+                editor_enter(&db, &state, &cursor, &mut editor, -1)
             }
             Command::ChangeCell => {
                 mode = Mode::Insert;
                 editor.insert_at("", 0);
+                Ok(())
             }
             Command::EditorExit
             | Command::EditorExitUp
             | Command::EditorExitDown
             | Command::EditorExitLeft
             | Command::EditorExitRight
-            | Command::EditorNewLine => {
-                mode = Mode::Normal;
-                if let Err(e) = editor_exit(&mut db, &state, &mut mode, &mut cursor, &mut editor) {
-                    set_error_message(&e.to_string(), &mut status_line_message, &mut mode);
-                }
-                if command != Command::EditorExit {
-                    if command == Command::EditorExitLeft && cursor.x > 1 {
-                        cursor.x -= 1;
-                    } else if command == Command::EditorExitRight {
-                        cursor.x += 1;
-                    } else if command == Command::EditorExitUp && cursor.y > 0 {
-                        cursor.y -= 1;
-                    } else if command == Command::EditorExitDown {
-                        cursor.y += 1;
-                    } else if command == Command::EditorNewLine && cursor.y > 0 {
-                        if let Err(e) = db.insert_empty_row_at(&state.table_name, cursor.y) {
-                            set_error_message(&e.to_string(), &mut status_line_message, &mut mode);
-                        }
-                        // set old_text to the cell's contents, or output error message
-                        let old_text =
-                            match db.select_at(&state.table_name, cursor.x - 1, cursor.y - 1) {
-                                Ok(cell) => cell.to_string(),
-                                Err(e) => {
-                                    set_error_message(
-                                        &e.to_string(),
-                                        &mut status_line_message,
-                                        &mut mode,
-                                    );
-                                    String::new()
-                                }
-                            };
-
-                        let leading_spaces = old_text.chars().take_while(|c| *c == ' ').count();
-                        cursor.y += 1;
-                        // Set cell to the leading spaces of the old cell
-                        let spaces = " ".repeat(leading_spaces);
-                        if let Err(e) = db.set_at(
-                            &state.table_name,
-                            cursor.y - 1,
-                            cursor.x - 1,
-                            Data::String(spaces),
-                        ) {
-                            set_error_message(&e.to_string(), &mut status_line_message, &mut mode);
-                        }
-                    }
-                    if command == Command::EditorNewLine && cursor.y == 0 {
-                        mode = Mode::Normal;
-                    } else {
-                        mode = Mode::Insert;
-                        editor_enter(&db, &state, &cursor, &mut editor, -1);
-                    }
-                }
-            }
+            | Command::EditorNewLine
+            | Command::EditorEnter
+            | Command::EditorShiftEnter => editor_exit_and_move(
+                &mut db,
+                &state,
+                &mut mode,
+                &mut cursor,
+                &mut editor,
+                &command,
+            ),
             Command::CommandLineEnter => {
                 mode = Mode::Command;
+                Ok(())
             }
             Command::CommandLineExit => {
                 let line = editor.get_line();
                 let mut args = line.split_whitespace();
-                if let Some(line_command) = args.next() {
+                let result = if let Some(line_command) = args.next() {
                     match line_command {
                         "q" => break,
-                        "e" => {
-                            load_table(
-                                &mut args,
-                                &mut state,
-                                &mut previous_table_name,
-                                &mut cursor,
-                                &mut db,
-                                &mut editor,
-                            );
-                            meta::insert_recent_table(&mut meta_db, &state).unwrap();
-                        }
+                        "e" => load_table(
+                            &mut args,
+                            &mut state,
+                            &mut previous_table_name,
+                            &mut cursor,
+                            &mut db,
+                            &mut editor,
+                        )
+                        .and_then(|_| meta::insert_recent_table(&mut meta_db, &state)),
                         "ls" => list_tables(
                             &mut state,
                             &mut previous_table_name,
@@ -290,33 +318,30 @@ fn main() {
                             &mut mode,
                         ),
                         "lsdb" => {
-                            list_databases(
+                            let result = list_databases(
                                 &mut state,
                                 &mut previous_table_name,
                                 &mut cursor,
                                 &mut db,
                                 &mut mode,
                             );
-                            renew_watch_descriptor!();
+                            result.and(renew_watch_descriptor!())
                         }
 
-                        "drop" => {
-                            if let Err(msg) = drop_table(
-                                args,
-                                &mut db,
-                                &mut state,
-                                &mut previous_table_name,
-                                &mut cursor,
-                                &mut mode,
-                            ) {
-                                set_error_message(&msg, &mut status_line_message, &mut mode);
-                            } else {
-                                consume_inotify_events(&mut inotify, buffer);
-                            }
-                            meta::insert_recent_table(&mut meta_db, &state).unwrap();
-                        }
+                        "drop" => drop_table(
+                            args,
+                            &mut db,
+                            &mut state,
+                            &mut previous_table_name,
+                            &mut cursor,
+                            &mut mode,
+                        )
+                        .and_then(|_| {
+                            consume_inotify_events(&mut inotify, buffer);
+                            meta::insert_recent_table(&mut meta_db, &state)
+                        }),
                         "cd" => {
-                            if let Some(arg1) = args.next() {
+                            let result = if let Some(arg1) = args.next() {
                                 if let Some(arg2) = args.next() {
                                     state.db_dir = arg1.to_string();
                                     state.db_name = arg2.to_string();
@@ -330,46 +355,59 @@ fn main() {
                                     &mut cursor,
                                     &mut db,
                                     &mut mode,
-                                );
+                                )
                             } else {
-                                set_error_message(
-                                    "usage: cd [<db_dir>] <dir>",
-                                    &mut status_line_message,
-                                    &mut mode,
-                                );
-                            }
-                            renew_watch_descriptor!();
-                        }
-                        "pwd" => {
-                            let new_table_name = ".".to_string();
-                            set_table(
-                                &new_table_name,
-                                &mut state,
-                                &mut previous_table_name,
-                                &mut cursor,
-                            );
-                            db.create_or_replace_table(&state.table_name).unwrap();
-                            db.create_column(&state.table_name, "name").unwrap();
-                            db.create_column(&state.table_name, "value").unwrap();
-                            db.insert(&state.table_name, vec!["database path", &state.db_dir])
-                                .unwrap();
-                            db.insert(&state.table_name, vec!["database name", &state.db_name])
-                                .unwrap();
-                            mode = Mode::ListReadOnly;
-                        }
-                        _ => {
-                            set_error_message(
-                                &format!("Unknown command: {}", line_command),
-                                &mut status_line_message,
-                                &mut mode,
-                            );
+                                Err("usage: cd [<db_dir>] <dir>".into())
+                            };
+                            result.and(renew_watch_descriptor!())
                         }
+                        "pwd" => print_working_directory(
+                            &mut state,
+                            &mut previous_table_name,
+                            &mut cursor,
+                            &mut db,
+                            &mut mode,
+                        ),
+                        "settype" => set_column_type(args, &mut db, &state, &cursor),
+                        "format" => set_column_format(args, &mut db, &state, &cursor),
+                        "fill" => fill_cells(args, &mut db, &state, &cursor, &selection),
+                        "sort" => sort_by_column(args, &mut db, &state, &cursor),
+                        "filter" => filter_by_checkbox(
+                            args,
+                            &mut state,
+                            &mut previous_table_name,
+                            &mut cursor,
+                            &mut db,
+                            &mut mode,
+                        ),
+                        "outline" => set_outline_column(args, &mut db, &state, &cursor),
+                        "checkstyle" => set_check_style(args, &mut db, &state),
+                        "enter" => set_enter_mode(args, &mut db, &state),
+                        "note" => set_note(args, &mut db, &state, &cursor),
+                        "rowid" => show_row_id(&mut db, &state, &cursor).map(|message| {
+                            set_error_message(&message, &mut status_line_message, &mut mode)
+                        }),
+                        "map" => keys::map(args, &mut meta_db, &mut key_map),
+                        "join" => set_column_join(args, &mut db, &state, &cursor),
+                        "agg" | "aggregate" => set_column_aggregate(args, &mut db, &state, &cursor),
+                        "reg" | "registers" => show_registers(
+                            &mut state,
+                            &mut previous_table_name,
+                            &mut cursor,
+                            &mut db,
+                            &mut mode,
+                            clipboard_table_name,
+                        ),
+                        _ => Err(format!("Unknown command: {}", line_command).into()),
                     }
-                }
+                } else {
+                    Ok(())
+                };
                 if mode == Mode::Command {
                     mode = Mode::Normal;
                 }
                 editor.clear();
+                result
             }
             Command::ListTablesEnter | Command::ListDatabasesEnter => {
                 if cursor.y > 0 {
@@ -391,8 +429,7 @@ fn main() {
                                         &mut cursor,
                                         &mut db,
                                         &mut mode,
-                                    );
-                                    mode = Mode::ListTables;
+                                    )
                                 }
                                 Command::ListTablesEnter => {
                                     set_table(
@@ -402,177 +439,76 @@ fn main() {
                                         &mut cursor,
                                     );
                                     mode = Mode::Normal;
-                                    meta::insert_recent_table(&mut meta_db, &state).unwrap();
+                                    meta::insert_recent_table(&mut meta_db, &state)
                                 }
                                 _ => unreachable!(),
                             }
+                        } else {
+                            Ok(())
                         }
-                    }
-                }
-            }
-            Command::PasteToday => {
-                if cursor.y > 0 {
-                    extend_table(&mut db, &state.table_name, cursor.x, cursor.y).unwrap();
-                    db.set_at(
-                        &state.table_name,
-                        cursor.y - 1,
-                        cursor.x - 1,
-                        Data::Date(Date::today()),
-                    )
-                    .unwrap();
-                }
-            }
-            Command::InsertEmptyColumn => {
-                let mut column_count = db.get_column_count(&state.table_name).unwrap();
-                while column_count < cursor.x {
-                    db.create_column(
-                        &state.table_name,
-                        &generate_column_name(&db, &state.table_name, column_count),
-                    )
-                    .unwrap();
-                    column_count += 1;
-                }
-                db.insert_column_at(
-                    &state.table_name,
-                    &generate_column_name(&db, &state.table_name, cursor.x),
-                    cursor.x - 1,
-                )
-                .unwrap();
-            }
-            Command::InsertEmptyRowAbove => {
-                if cursor.y > 0 && is_cell(&db, &state, 0, cursor.y - 1) {
-                    db.insert_empty_row_at(&state.table_name, cursor.y - 1)
-                        .unwrap();
-                }
-            }
-            Command::InsertEmptyRowBelow => {
-                if cursor.y > 0 && is_cell(&db, &state, 0, cursor.y) {
-                    db.insert_empty_row_at(&state.table_name, cursor.y).unwrap();
-                }
-                cursor.y += 1;
-            }
-            Command::DeleteCell => {
-                let (x, y) = (cursor.x, cursor.y);
-                yank(
-                    Rect {
-                        start_x: x,
-                        end_x: x + 1,
-                        start_y: y,
-                        end_y: y + 1,
-                    },
-                    &mut db,
-                    &state,
-                    clipboard_table_name,
-                    &mut clipboard,
-                );
-                if cursor.y > 0 {
-                    if is_cell(&db, &state, cursor.x - 1, cursor.y - 1) {
-                        db.set_at(&state.table_name, cursor.y - 1, cursor.x - 1, Data::Empty)
-                            .unwrap();
+                    } else {
+                        Ok(())
                     }
                 } else {
-                    let old_column_name = db
-                        .get_column_name_at(&state.table_name, cursor.x - 1)
-                        .unwrap();
-                    let generic_column_name =
-                        generate_column_name(&db, &state.table_name, cursor.x - 1);
-                    db.rename_column(&state.table_name, &old_column_name, &generic_column_name)
-                        .unwrap();
+                    Ok(())
                 }
             }
-            Command::DeleteLine => {
-                if cursor.y > 0 && is_cell(&db, &state, 0, cursor.y - 1) {
-                    let num_columns = db.get_column_count(&state.table_name).unwrap();
-                    yank(
-                        Rect {
-                            start_x: 1,
-                            end_x: num_columns,
-                            start_y: cursor.y,
-                            end_y: cursor.y + 1,
-                        },
-                        &mut db,
-                        &state,
-                        clipboard_table_name,
-                        &mut clipboard,
-                    );
-                    db.delete_row_at(&state.table_name, cursor.y - 1).unwrap();
-                }
-                if cursor.y > 1 && cursor.y > db.get_row_count(&state.table_name).unwrap() {
-                    cursor.y -= 1;
-                }
+            Command::PasteToday => paste_today(&mut db, &state, &cursor),
+            Command::PasteNow => paste_now(&mut db, &state, &cursor),
+            Command::Increment(amount) => increment_cell(&mut db, &state, &cursor, amount),
+            Command::ToggleFold => outline::toggle_fold(&mut db, &state, &cursor, &mut folds),
+            Command::FoldAll => outline::fold_all(&mut db, &state, &mut cursor, &mut folds),
+            Command::UnfoldAll => {
+                outline::unfold_all(&state, &mut folds);
+                Ok(())
             }
-            Command::DeleteColumn => {
-                if is_cell(&db, &state, cursor.x - 1, 0) {
-                    let num_rows = db.get_row_count(&state.table_name).unwrap();
-                    yank(
-                        Rect {
-                            start_x: cursor.x,
-                            end_x: cursor.x + 1,
-                            start_y: 1,
-                            end_y: num_rows,
-                        },
-                        &mut db,
-                        &state,
-                        clipboard_table_name,
-                        &mut clipboard,
-                    );
-                    db.delete_column(
-                        &state.table_name,
-                        &db.get_column_name_at(&state.table_name, cursor.x - 1)
-                            .unwrap(),
-                    )
-                    .unwrap();
-                }
+            Command::MoveSubtreeUp => outline::move_subtree(&mut db, &state, &mut cursor, false),
+            Command::MoveSubtreeDown => outline::move_subtree(&mut db, &state, &mut cursor, true),
+            Command::IndentSubtreeLeft => outline::indent_subtree(&mut db, &state, &cursor, true),
+            Command::IndentSubtreeRight => outline::indent_subtree(&mut db, &state, &cursor, false),
+            Command::ToggleCheckbox => {
+                checkbox::toggle(&mut db, &state.table_name, &cursor, &selection)
             }
-
-            Command::IndentLeft | Command::IndentRight => {
-                editor_enter(&db, &state, &cursor, &mut editor, -1);
-                if command == Command::IndentLeft {
-                    editor.indent_left();
-                } else {
-                    editor.indent_right();
-                }
-                if let Err(e) = editor_exit(&mut db, &state, &mut mode, &mut cursor, &mut editor) {
-                    set_error_message(
-                        &format!("Error in indentation: {}", e),
-                        &mut status_line_message,
-                        &mut mode,
-                    );
-                }
+            Command::InsertEmptyColumn => insert_empty_column(&mut db, &state, &cursor),
+            Command::InsertEmptyRowAbove => insert_empty_row(&mut db, &state, &mut cursor, false),
+            Command::InsertEmptyRowBelow => insert_empty_row(&mut db, &state, &mut cursor, true),
+            Command::Operate(operator, motion, count) => operate(
+                &mut db,
+                &state,
+                &mut mode,
+                &mut cursor,
+                &mut editor,
+                clipboard_table_name,
+                &mut clipboard,
+                register,
+                operator,
+                motion,
+                count,
+            ),
+            Command::Fill(fill_mode) => fill::fill(&mut db, &state, &cursor, &selection, fill_mode),
+            Command::Move(motion, count) => {
+                move_cells(&mut db, &state, &mut cursor, &mut selection, motion, count)
             }
-
-            Command::YankCell | Command::YankRow | Command::YankColumn => {
-                let (start_x, end_x, start_y, end_y) = match command {
-                    Command::YankCell => (cursor.x, cursor.x + 1, cursor.y, cursor.y + 1),
-                    Command::YankRow => (
-                        1,
-                        db.get_column_count(&state.table_name).unwrap() + 1,
-                        cursor.y,
-                        cursor.y + 1,
-                    ),
-                    Command::YankColumn => (
-                        cursor.x,
-                        cursor.x + 1,
-                        1,
-                        db.get_row_count(&state.table_name).unwrap() + 1,
-                    ),
-                    _ => unreachable!(),
+            Command::PasteReplace | Command::PasteBefore | Command::PasteAfter => {
+                // text copied in another program is pasted as a block of cells
+                let imported = if register.is_none() {
+                    clipboard_from_clipboard(&mut db, clipboard_table_name, &mut clipboard)
+                } else {
+                    Ok(())
                 };
-                yank(
-                    Rect {
-                        start_x,
-                        end_x,
-                        start_y,
-                        end_y,
-                    },
-                    &mut db,
-                    &state,
-                    clipboard_table_name,
-                    &mut clipboard,
-                )
+                imported
+                    .and_then(|_| registers::source_table(&db, register, clipboard_table_name))
+                    .and_then(|source_table_name| {
+                        paste(&mut db, &state, &source_table_name, &mut cursor, &command)
+                    })
             }
-            Command::PasteReplace | Command::PasteBefore | Command::PasteAfter => {
-                paste(&mut db, &state, clipboard_table_name, &mut cursor, &command)
+        };
+        if let Err(e) = result {
+            if mode == Mode::Insert {
+                // the editor stays open, the message is shown below it
+                status_line_message = e.to_string();
+            } else {
+                set_error_message(&e.to_string(), &mut status_line_message, &mut mode);
             }
         }
 
@@ -592,6 +528,25 @@ fn main() {
     render::cleanup();
 }
 
+fn create_default_table(db: &mut Db, table_name: &str) -> error::Result<()> {
+    db.create_table(table_name)?;
+    db.create_column(table_name, "date")?;
+    db.create_column(table_name, "topic")?;
+    db.create_column(table_name, "done")?;
+    column_type::set(db, table_name, "done", Some(column_type::ColumnType::Bool))?;
+    Ok(())
+}
+
+fn add_watch(inotify: &mut Inotify, db: &Db, state: &State) -> error::Result<WatchDescriptor> {
+    let path = format!("{}/{}", db.get_db_path(), state.db_name);
+    inotify
+        .add_watch(
+            &path,
+            WatchMask::MODIFY | WatchMask::CREATE | WatchMask::DELETE,
+        )
+        .map_err(|e| format!("Can't watch {} for changes: {}", path, e).into())
+}
+
 fn consume_inotify_events(inotify: &mut Inotify, mut buffer: [u8; 1024]) {
     // consume events generated by save()
     let _ = inotify.read_events(&mut buffer);
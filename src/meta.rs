@@ -1,12 +1,10 @@
+use crate::error::Result;
 use crate::State;
 use rzdb::{Condition, ConditionType, Data, Db};
 
 const _RECENT_TABLES: &str = "recent_tables";
 
-pub(crate) fn insert_recent_table(
-    meta_db: &mut Db,
-    state: &State,
-) -> Result<(), Box<dyn std::error::Error>> {
+pub(crate) fn insert_recent_table(meta_db: &mut Db, state: &State) -> Result<()> {
     // create recents table if it doesn't exist
     if !meta_db.exists(_RECENT_TABLES) {
         meta_db.create_table(_RECENT_TABLES)?;
@@ -43,3 +41,41 @@ pub(crate) fn insert_recent_table(
     meta_db.save()?;
     Ok(())
 }
+
+const _KEYS: &str = "keys";
+
+// key bound to an editor action with :map, None for the default
+pub(crate) fn get_key(meta_db: &Db, action: &str) -> Option<String> {
+    if !meta_db.exists(_KEYS) {
+        return None;
+    }
+    meta_db
+        .select_from(_KEYS)
+        .ok()?
+        .iter()
+        .find(|row| {
+            row.select_at(0)
+                .is_ok_and(|data| data.to_string() == action)
+        })
+        .and_then(|row| row.select_at(1).ok())
+        .map(|data| data.to_string())
+}
+
+pub(crate) fn set_key(meta_db: &mut Db, action: &str, key: &str) -> Result<()> {
+    if !meta_db.exists(_KEYS) {
+        meta_db.create_table(_KEYS)?;
+        meta_db.create_column(_KEYS, "action")?;
+        meta_db.create_column(_KEYS, "key")?;
+    }
+    meta_db.delete_where(
+        _KEYS,
+        &[Condition::new(
+            "action",
+            Data::String(action.to_string()),
+            ConditionType::Equal,
+        )],
+    )?;
+    meta_db.insert(_KEYS, vec![action, key])?;
+    meta_db.save()?;
+    Ok(())
+}
@@ -2,9 +2,11 @@
 pub enum Mode {
     Normal,
     Insert,
+    Calendar,
     Yank,
     Command,
     Delete,
+    Change,
     ListTables,
     ListDatabases,
     ListReadOnly,
@@ -25,8 +27,10 @@ impl std::fmt::Display for Mode {
             match self {
                 Mode::Normal => "Normal".to_string(),
                 Mode::Insert => "Insert".to_string(),
+                Mode::Calendar => "Calendar".to_string(),
                 Mode::Yank => "Yank".to_string(),
                 Mode::Delete => "Delete".to_string(),
+                Mode::Change => "Change".to_string(),
                 Mode::Command => "Command".to_string(),
                 Mode::ListTables => "List Tables".to_string(),
                 Mode::ListDatabases => "List Databases".to_string(),
@@ -0,0 +1,174 @@
+use rzdb::{Data, Db};
+
+use crate::command::Motion;
+use crate::common::Rect;
+use crate::pos::Pos;
+use crate::State;
+
+#[derive(PartialEq, Eq)]
+pub enum RangeKind {
+    Cells,
+    Rows,
+    Columns,
+}
+
+// x/y are 0-indexed
+fn is_empty_cell(db: &Db, state: &State, x: usize, y: usize) -> bool {
+    match db.select_at(&state.table_name, x, y) {
+        Ok(Data::Empty) | Err(_) => true,
+        Ok(data) => data.to_string().trim().is_empty(),
+    }
+}
+
+// y is 0-indexed
+fn is_blank_row(db: &Db, state: &State, y: usize) -> bool {
+    let column_count = db.get_column_count(&state.table_name).unwrap_or(0);
+    (0..column_count).all(|x| is_empty_cell(db, state, x, y))
+}
+
+// next (or previous) non-empty cell in reading order, x/y are 1-indexed
+fn next_word(db: &Db, state: &State, from: Pos, forward: bool) -> Pos {
+    let column_count = db.get_column_count(&state.table_name).unwrap_or(0);
+    let row_count = db.get_row_count(&state.table_name).unwrap_or(0);
+    if column_count == 0 || row_count == 0 {
+        return from;
+    }
+    let cell_count = column_count * row_count;
+    let from_idx =
+        (from.y.max(1) - 1).min(row_count - 1) * column_count + (from.x - 1).min(column_count - 1);
+    let found = if forward {
+        (from_idx + 1..cell_count)
+            .find(|idx| !is_empty_cell(db, state, idx % column_count, idx / column_count))
+    } else {
+        (0..from_idx)
+            .rev()
+            .find(|idx| !is_empty_cell(db, state, idx % column_count, idx / column_count))
+    };
+    match found {
+        Some(idx) => Pos::new(idx % column_count + 1, idx / column_count + 1),
+        None => from,
+    }
+}
+
+// first blank row after (or before) the current block of rows, y is 1-indexed
+fn next_paragraph(db: &Db, state: &State, from_y: usize, forward: bool) -> usize {
+    let row_count = db.get_row_count(&state.table_name).unwrap_or(0);
+    if forward {
+        let mut y = from_y + 1;
+        while y <= row_count && is_blank_row(db, state, y - 1) {
+            y += 1;
+        }
+        while y <= row_count && !is_blank_row(db, state, y - 1) {
+            y += 1;
+        }
+        y.min(row_count.max(1))
+    } else {
+        let mut y = from_y.saturating_sub(1).min(row_count);
+        while y > 1 && is_blank_row(db, state, y - 1) {
+            y -= 1;
+        }
+        while y > 1 && !is_blank_row(db, state, y - 1) {
+            y -= 1;
+        }
+        y.max(1)
+    }
+}
+
+// cursor position after applying a motion count times
+pub(crate) fn target(db: &Db, state: &State, cursor: &Pos, motion: Motion, count: usize) -> Pos {
+    let count = count.max(1);
+    let mut pos = *cursor;
+    match motion {
+        Motion::Left => pos.x = cursor.x.saturating_sub(count).max(1),
+        Motion::Right => pos.x = cursor.x.saturating_add(count),
+        Motion::Up => {
+            pos.y = cursor.y.saturating_sub(count);
+            // like page up, a long way up stops at the first row instead of the header
+            if count > 2 && cursor.y > 0 {
+                pos.y = pos.y.max(1);
+            }
+        }
+        Motion::Down => pos.y = cursor.y.saturating_add(count),
+        // the word and paragraph motions stop early once there's nothing further
+        Motion::WordForward | Motion::WordBackward => {
+            for _ in 0..count {
+                let next = next_word(db, state, pos, motion == Motion::WordForward);
+                if next == pos {
+                    break;
+                }
+                pos = next;
+            }
+        }
+        Motion::ParagraphForward | Motion::ParagraphBackward => {
+            for _ in 0..count {
+                let y = next_paragraph(db, state, pos.y, motion == Motion::ParagraphForward);
+                if y == pos.y {
+                    break;
+                }
+                pos.y = y;
+            }
+        }
+        Motion::FirstRow => pos.y = 1,
+        Motion::LastRow => pos.y = db.get_row_count(&state.table_name).unwrap_or(0).max(1),
+        Motion::Row(y) => pos.y = y.max(1),
+        Motion::Line => pos.y = cursor.y.saturating_add(count - 1),
+        Motion::Column => pos.x = cursor.x.saturating_add(count - 1),
+    }
+    pos
+}
+
+// range an operator works on, x/y are 1-indexed, ends are exclusive
+pub(crate) fn range(
+    db: &Db,
+    state: &State,
+    cursor: &Pos,
+    motion: Motion,
+    count: usize,
+) -> (Rect, RangeKind) {
+    let column_count = db.get_column_count(&state.table_name).unwrap_or(0);
+    let row_count = db.get_row_count(&state.table_name).unwrap_or(0);
+    let to = target(db, state, cursor, motion, count);
+    if motion == Motion::Column {
+        let rect = Rect {
+            start_x: cursor.x,
+            end_x: to.x + 1,
+            start_y: 1,
+            end_y: row_count + 1,
+        };
+        return (rect, RangeKind::Columns);
+    }
+    if motion.is_linewise() {
+        let (from_y, mut to_y) = (cursor.y.max(1), to.y.max(1));
+        // like in vim, { and } don't include the blank row they stop at
+        let paragraph = matches!(motion, Motion::ParagraphForward | Motion::ParagraphBackward);
+        if paragraph && to_y != from_y && to_y <= row_count && is_blank_row(db, state, to_y - 1) {
+            if to_y > from_y {
+                to_y -= 1;
+            } else {
+                to_y += 1;
+            }
+        }
+        let rect = Rect {
+            start_x: 1,
+            end_x: column_count + 1,
+            start_y: from_y.min(to_y),
+            end_y: from_y.max(to_y) + 1,
+        };
+        return (rect, RangeKind::Rows);
+    }
+    // cell motions are exclusive and stay in the cursor's row, as dw and dl in vim
+    let (start_x, end_x) = if to.y > cursor.y {
+        (cursor.x, column_count + 1)
+    } else if to.y < cursor.y {
+        (1, cursor.x)
+    } else {
+        (cursor.x.min(to.x), cursor.x.max(to.x))
+    };
+    let rect = Rect {
+        start_x,
+        end_x,
+        start_y: cursor.y,
+        end_y: cursor.y + 1,
+    };
+    (rect, RangeKind::Cells)
+}
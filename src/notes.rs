@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use rzdb::{Data, Db};
+
+use crate::error::Result;
+use crate::pos::Pos;
+use crate::rowid::{self, RowId};
+
+// comments on cells, stored in the database next to the tables like the settings.
+// a note belongs to a row id and a column name, so it follows moved rows and renamed columns.
+pub(crate) const NOTES_TABLE: &str = ".notes";
+const NOTES_COLUMNS: [&str; 4] = ["table_name", "row_id", "column_name", "text"];
+
+fn cell(db: &Db, x: usize, y: usize) -> String {
+    db.select_at(NOTES_TABLE, x, y)
+        .map(|data| data.to_string())
+        .unwrap_or_default()
+}
+
+fn find(db: &Db, table_name: &str, row_id: RowId, column_name: &str) -> Option<usize> {
+    if !db.exists(NOTES_TABLE) {
+        return None;
+    }
+    let row = row_id.to_string();
+    (0..db.get_row_count(NOTES_TABLE).unwrap_or(0)).find(|&idx| {
+        cell(db, 0, idx) == table_name && cell(db, 1, idx) == row && cell(db, 2, idx) == column_name
+    })
+}
+
+// an empty text removes the note
+pub(crate) fn set(
+    db: &mut Db,
+    table_name: &str,
+    row_id: RowId,
+    column_name: &str,
+    text: &str,
+) -> Result<()> {
+    if !db.exists(NOTES_TABLE) {
+        db.create_table(NOTES_TABLE)?;
+        for column in NOTES_COLUMNS {
+            db.create_column(NOTES_TABLE, column)?;
+        }
+    }
+    match find(db, table_name, row_id, column_name) {
+        Some(idx) if text.is_empty() => db.delete_row_at(NOTES_TABLE, idx)?,
+        Some(idx) => db.set_at(NOTES_TABLE, idx, 3, Data::String(text.to_string()))?,
+        None if text.is_empty() => {}
+        None => {
+            let idx = db.get_row_count(NOTES_TABLE)?;
+            db.insert(NOTES_TABLE, vec![""; NOTES_COLUMNS.len()])?;
+            let row = row_id.to_string();
+            for (x, value) in [table_name, &row, column_name, text].iter().enumerate() {
+                db.set_at(NOTES_TABLE, idx, x, Data::String(value.to_string()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// notes follow their column when it's renamed
+pub(crate) fn rename_column(
+    db: &mut Db,
+    table_name: &str,
+    old_column_name: &str,
+    new_column_name: &str,
+) -> Result<()> {
+    if !db.exists(NOTES_TABLE) {
+        return Ok(());
+    }
+    for idx in 0..db.get_row_count(NOTES_TABLE)? {
+        if cell(db, 0, idx) == table_name && cell(db, 2, idx) == old_column_name {
+            let name = Data::String(new_column_name.to_string());
+            db.set_at(NOTES_TABLE, idx, 2, name)?;
+        }
+    }
+    Ok(())
+}
+
+// notes of deleted rows, columns and tables go with them
+fn remove_where(db: &mut Db, matches: impl Fn(&Db, usize) -> bool) -> Result<()> {
+    if !db.exists(NOTES_TABLE) {
+        return Ok(());
+    }
+    for idx in (0..db.get_row_count(NOTES_TABLE)?).rev() {
+        if matches(db, idx) {
+            db.delete_row_at(NOTES_TABLE, idx)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn remove_row(db: &mut Db, table_name: &str, row_id: RowId) -> Result<()> {
+    let row = row_id.to_string();
+    remove_where(db, |db, idx| {
+        cell(db, 0, idx) == table_name && cell(db, 1, idx) == row
+    })
+}
+
+pub(crate) fn remove_column(db: &mut Db, table_name: &str, column_name: &str) -> Result<()> {
+    remove_where(db, |db, idx| {
+        cell(db, 0, idx) == table_name && cell(db, 2, idx) == column_name
+    })
+}
+
+pub(crate) fn remove_table(db: &mut Db, table_name: &str) -> Result<()> {
+    remove_where(db, |db, idx| cell(db, 0, idx) == table_name)
+}
+
+// notes of a table by cell position
+pub(crate) fn get_all(db: &Db, table_name: &str) -> HashMap<Pos, String> {
+    let mut notes = HashMap::new();
+    if !db.exists(NOTES_TABLE) {
+        return notes;
+    }
+    let column_names = db.get_column_names(table_name).unwrap_or_default();
+    let row_ids = rowid::get_all(db, table_name);
+    for idx in 0..db.get_row_count(NOTES_TABLE).unwrap_or(0) {
+        if cell(db, 0, idx) != table_name {
+            continue;
+        }
+        let x = column_names
+            .iter()
+            .position(|column_name| *column_name == cell(db, 2, idx));
+        let row_id = cell(db, 1, idx).parse().ok();
+        let y = row_ids.iter().position(|id| id.is_some() && *id == row_id);
+        if let (Some(x), Some(y)) = (x, y) {
+            notes.insert(Pos::new(x + 1, y + 1), cell(db, 3, idx));
+        }
+    }
+    notes
+}
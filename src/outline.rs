@@ -0,0 +1,327 @@
+use std::collections::BTreeSet;
+
+use rzdb::Db;
+
+use crate::column_type;
+use crate::common;
+use crate::error::Result;
+use crate::pos::Pos;
+use crate::rowid::{self, RowId};
+use crate::settings;
+use crate::State;
+
+// the outline column of a table treats the leading spaces of its cells as tree depth.
+// all row numbers here are 1-indexed like the cursor.
+const OUTLINE_KEY: &str = "outline";
+const INDENT_SIZE: usize = 2;
+
+// folded rows of the current table by row id, so that folds stay with their rows when
+// rows are inserted, deleted or moved. their subtrees are hidden.
+#[derive(Default)]
+pub(crate) struct Folds {
+    table_name: String,
+    row_ids: BTreeSet<RowId>,
+}
+
+impl Folds {
+    // row numbers of the folded rows, look them up once and not per row
+    pub fn rows(&self, db: &Db, table_name: &str) -> BTreeSet<usize> {
+        if self.table_name != table_name {
+            return BTreeSet::new();
+        }
+        rowid::get_all(db, table_name)
+            .iter()
+            .enumerate()
+            .filter(|(_, row_id)| row_id.is_some_and(|row_id| self.row_ids.contains(&row_id)))
+            .map(|(idx, _)| idx + 1)
+            .collect()
+    }
+
+    fn row_ids_mut(&mut self, table_name: &str) -> &mut BTreeSet<RowId> {
+        if self.table_name != table_name {
+            self.table_name = table_name.to_string();
+            self.row_ids.clear();
+        }
+        &mut self.row_ids
+    }
+
+    pub fn is_folded(&self, db: &Db, table_name: &str, y: usize) -> bool {
+        self.rows(db, table_name).contains(&y)
+    }
+}
+
+fn indent(text: &str) -> usize {
+    text.chars().take_while(|ch| *ch == ' ').count()
+}
+
+fn is_blank(text: &str) -> bool {
+    text.trim().is_empty()
+}
+
+// end (exclusive) of the subtree starting at row y: all following rows that are indented
+// deeper, blank rows only if more of the subtree follows
+pub(crate) fn subtree_end(texts: &[String], y: usize) -> usize {
+    let text = &texts[y - 1];
+    if is_blank(text) {
+        return y + 1;
+    }
+    let mut last = y;
+    for (idx, other) in texts.iter().enumerate().skip(y) {
+        if is_blank(other) {
+            continue;
+        }
+        if indent(other) <= indent(text) {
+            break;
+        }
+        last = idx + 1;
+    }
+    last + 1
+}
+
+pub(crate) fn has_children(texts: &[String], y: usize) -> bool {
+    y >= 1 && y <= texts.len() && subtree_end(texts, y) > y + 1
+}
+
+// rows that aren't hidden inside a folded subtree
+pub(crate) fn visible_rows(texts: &[String], folded: &BTreeSet<usize>) -> Vec<usize> {
+    let mut rows = vec![];
+    let mut y = 1;
+    while y <= texts.len() {
+        rows.push(y);
+        y = if folded.contains(&y) {
+            subtree_end(texts, y)
+        } else {
+            y + 1
+        };
+    }
+    rows
+}
+
+pub(crate) fn column(db: &Db, table_name: &str) -> Option<usize> {
+    let column_name = settings::get(db, table_name, "", OUTLINE_KEY)?;
+    db.get_column_names(table_name)
+        .ok()?
+        .iter()
+        .position(|name| *name == column_name)
+}
+
+// None turns the outline off
+pub(crate) fn set_column(db: &mut Db, table_name: &str, column_name: Option<&str>) -> Result<()> {
+    settings::set(
+        db,
+        table_name,
+        "",
+        OUTLINE_KEY,
+        column_name.unwrap_or_default(),
+    )
+}
+
+// text of every row in column x
+pub(crate) fn texts(db: &Db, table_name: &str, x: usize) -> Vec<String> {
+    (0..db.get_row_count(table_name).unwrap_or(0))
+        .map(|y| {
+            db.select_at(table_name, x, y)
+                .map(|data| data.to_string())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+// texts of the outline column, None if the table has no outline
+pub(crate) fn outline_texts(db: &Db, table_name: &str) -> Option<Vec<String>> {
+    column(db, table_name).map(|x| texts(db, table_name, x))
+}
+
+// the rows the screen shows, in order
+pub(crate) fn visible(db: &Db, table_name: &str, folded: &BTreeSet<usize>) -> Vec<usize> {
+    let row_count = db.get_row_count(table_name).unwrap_or(0);
+    match outline_texts(db, table_name) {
+        Some(texts) if !folded.is_empty() => visible_rows(&texts, folded),
+        _ => (1..=row_count).collect(),
+    }
+}
+
+// move the cursor off hidden rows, past the fold when moving down, onto it when moving up
+pub(crate) fn skip_hidden(
+    db: &Db,
+    table_name: &str,
+    folds: &Folds,
+    cursor: &mut Pos,
+    previous_y: usize,
+) {
+    let row_count = db.get_row_count(table_name).unwrap_or(0);
+    if cursor.y == 0 || cursor.y > row_count {
+        return;
+    }
+    let visible = visible(db, table_name, &folds.rows(db, table_name));
+    if visible.contains(&cursor.y) {
+        return;
+    }
+    let above = visible.iter().rev().find(|y| **y < cursor.y).copied();
+    let below = visible.iter().find(|y| **y > cursor.y).copied();
+    cursor.y = if cursor.y > previous_y {
+        below.unwrap_or(row_count + 1)
+    } else {
+        above.unwrap_or(1)
+    };
+}
+
+// the configured outline column, or the cursor column which then becomes the outline column
+fn outline_column(db: &mut Db, state: &State, cursor: &Pos) -> Result<usize> {
+    if let Some(x) = column(db, &state.table_name) {
+        return Ok(x);
+    }
+    let column_name = common::get_column_name_or_generic(cursor.x, db, &state.table_name)?;
+    set_column(db, &state.table_name, Some(&column_name))?;
+    Ok(cursor.x - 1)
+}
+
+// za
+pub(crate) fn toggle_fold(
+    db: &mut Db,
+    state: &State,
+    cursor: &Pos,
+    folds: &mut Folds,
+) -> Result<()> {
+    let x = outline_column(db, state, cursor)?;
+    let texts = texts(db, &state.table_name, x);
+    if !has_children(&texts, cursor.y) && !folds.is_folded(db, &state.table_name, cursor.y) {
+        return Err("Nothing to fold here".into());
+    }
+    let row_id = rowid::get(db, &state.table_name, cursor.y)?;
+    let row_ids = folds.row_ids_mut(&state.table_name);
+    if !row_ids.remove(&row_id) {
+        row_ids.insert(row_id);
+    }
+    Ok(())
+}
+
+// zM
+pub(crate) fn fold_all(
+    db: &mut Db,
+    state: &State,
+    cursor: &mut Pos,
+    folds: &mut Folds,
+) -> Result<()> {
+    let x = outline_column(db, state, cursor)?;
+    let texts = texts(db, &state.table_name, x);
+    let ids = rowid::sync(db, &state.table_name)?;
+    let rows: BTreeSet<usize> = (1..=texts.len())
+        .filter(|y| has_children(&texts, *y))
+        .collect();
+    let row_ids = folds.row_ids_mut(&state.table_name);
+    row_ids.clear();
+    row_ids.extend(rows.iter().map(|y| ids[y - 1]));
+    // the cursor moves up to the top level row it belongs to
+    let visible = visible_rows(&texts, &rows);
+    if cursor.y > 0 && cursor.y <= texts.len() {
+        cursor.y = visible
+            .iter()
+            .rev()
+            .find(|y| **y <= cursor.y)
+            .copied()
+            .unwrap_or(1);
+    }
+    Ok(())
+}
+
+// zR
+pub(crate) fn unfold_all(state: &State, folds: &mut Folds) {
+    folds.row_ids_mut(&state.table_name).clear();
+}
+
+// zj/zk: swap the subtree under the cursor with the next/previous sibling subtree
+pub(crate) fn move_subtree(db: &mut Db, state: &State, cursor: &mut Pos, down: bool) -> Result<()> {
+    let x = outline_column(db, state, cursor)?;
+    let texts = texts(db, &state.table_name, x);
+    if cursor.y == 0 || cursor.y > texts.len() || is_blank(&texts[cursor.y - 1]) {
+        return Err("No outline row under the cursor".into());
+    }
+    let depth = indent(&texts[cursor.y - 1]);
+    let end = subtree_end(&texts, cursor.y);
+    if down {
+        let sibling = (end..=texts.len()).find(|y| !is_blank(&texts[y - 1]));
+        match sibling {
+            Some(sibling) if indent(&texts[sibling - 1]) == depth => {
+                let sibling_end = subtree_end(&texts, sibling);
+                // folds move along with their row ids
+                common::rotate_rows(
+                    db,
+                    &state.table_name,
+                    cursor.y - 1,
+                    end - 1,
+                    sibling_end - 1,
+                )?;
+                cursor.y += sibling_end - end;
+                Ok(())
+            }
+            _ => Err("No sibling below to swap with".into()),
+        }
+    } else {
+        // skip the rows of the previous sibling's subtree
+        let sibling = (1..cursor.y)
+            .rev()
+            .find(|y| !is_blank(&texts[y - 1]) && indent(&texts[y - 1]) <= depth);
+        match sibling {
+            Some(sibling) if indent(&texts[sibling - 1]) == depth => {
+                common::rotate_rows(db, &state.table_name, sibling - 1, cursor.y - 1, end - 1)?;
+                cursor.y = sibling;
+                Ok(())
+            }
+            _ => Err("No sibling above to swap with".into()),
+        }
+    }
+}
+
+// z> / z<: indent or outdent the subtree under the cursor
+pub(crate) fn indent_subtree(db: &mut Db, state: &State, cursor: &Pos, left: bool) -> Result<()> {
+    let x = outline_column(db, state, cursor)?;
+    let texts = texts(db, &state.table_name, x);
+    if cursor.y == 0 || cursor.y > texts.len() {
+        return Err("No outline row under the cursor".into());
+    }
+    if left && indent(&texts[cursor.y - 1]) < INDENT_SIZE {
+        return Err("Already at the top level".into());
+    }
+    let column_name = db.get_column_name_at(&state.table_name, x)?;
+    let column_type = column_type::get(db, &state.table_name, &column_name);
+    for y in cursor.y..subtree_end(&texts, cursor.y) {
+        let text = &texts[y - 1];
+        if is_blank(text) {
+            continue;
+        }
+        let new_text = if left {
+            text[indent(text).min(INDENT_SIZE)..].to_string()
+        } else {
+            " ".repeat(INDENT_SIZE) + text
+        };
+        db.set_at(
+            &state.table_name,
+            y - 1,
+            x,
+            column_type::parse_text(column_type.as_ref(), &new_text),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::strings;
+
+    #[test]
+    fn test_subtree() {
+        let t = strings(&["a", "  b", "    c", "", "  d", "e", "", "f"]);
+        assert_eq!(subtree_end(&t, 1), 6);
+        assert_eq!(subtree_end(&t, 2), 4);
+        assert_eq!(subtree_end(&t, 3), 4);
+        assert_eq!(subtree_end(&t, 6), 7);
+        assert!(has_children(&t, 1));
+        assert!(!has_children(&t, 5));
+        let folded = BTreeSet::from([1, 2]);
+        assert_eq!(visible_rows(&t, &folded), vec![1, 6, 7, 8]);
+        let folded = BTreeSet::from([2]);
+        assert_eq!(visible_rows(&t, &folded), vec![1, 2, 4, 5, 6, 7, 8]);
+    }
+}
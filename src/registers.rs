@@ -0,0 +1,130 @@
+use rzdb::Db;
+
+use crate::common::extend_table;
+use crate::error::Result;
+
+// "1 holds the latest delete, "9 the oldest one
+const RING_SIZE: usize = 9;
+pub(crate) const REGISTER_PREFIX: &str = ".register.";
+
+pub(crate) fn is_register(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ('1'..='9').contains(&ch)
+}
+
+pub(crate) fn table_name(register: char) -> String {
+    format!("{}{}", REGISTER_PREFIX, register.to_ascii_lowercase())
+}
+
+// table to paste from, the unnamed register is the clipboard table itself
+pub(crate) fn source_table(
+    db: &Db,
+    register: Option<char>,
+    clipboard_table_name: &str,
+) -> Result<String> {
+    match register {
+        Some(register) => {
+            let name = table_name(register);
+            if db.exists(&name) {
+                Ok(name)
+            } else {
+                Err(format!("Register {} is empty", register).into())
+            }
+        }
+        None => Ok(clipboard_table_name.to_string()),
+    }
+}
+
+fn copy_table(db: &mut Db, from: &str, to: &str) -> Result<()> {
+    let columns = db.get_column_names(from)?;
+    let columns: Vec<&str> = columns.iter().map(|s| &**s).collect();
+    let row_count = db.get_row_count(from)?;
+    db.select_into(to, from, &columns, 0, row_count)?;
+    Ok(())
+}
+
+fn append_table(db: &mut Db, from: &str, to: &str) -> Result<()> {
+    let tmp_table_name = format!("{}tmp", REGISTER_PREFIX);
+    copy_table(db, from, &tmp_table_name)?;
+    let column_count = db
+        .get_column_count(&tmp_table_name)?
+        .max(db.get_column_count(to)?);
+    extend_table(db, &tmp_table_name, column_count, 0)?;
+    extend_table(db, to, column_count, 0)?;
+    let row_count = db.get_row_count(to)?;
+    db.insert_into_at(&tmp_table_name, to, row_count)?;
+    db.drop_table(&tmp_table_name)?;
+    Ok(())
+}
+
+// copy what was just yanked into the clipboard table into a named register,
+// deletes without a register go into the numbered ring
+pub(crate) fn store(
+    db: &mut Db,
+    clipboard_table_name: &str,
+    register: Option<char>,
+    deleted: bool,
+) -> Result<()> {
+    match register {
+        Some(register) => {
+            let name = table_name(register);
+            if register.is_ascii_uppercase() && db.exists(&name) {
+                append_table(db, clipboard_table_name, &name)
+            } else {
+                copy_table(db, clipboard_table_name, &name)
+            }
+        }
+        None if deleted => {
+            for idx in (2..=RING_SIZE).rev() {
+                let older = table_name(char::from_digit(idx as u32 - 1, 10).unwrap());
+                if db.exists(&older) {
+                    copy_table(
+                        db,
+                        &older,
+                        &table_name(char::from_digit(idx as u32, 10).unwrap()),
+                    )?;
+                }
+            }
+            copy_table(db, clipboard_table_name, &table_name('1'))
+        }
+        None => Ok(()),
+    }
+}
+
+fn preview(db: &Db, table_name: &str) -> String {
+    let mut preview = String::new();
+    if let Ok(rows) = db.select_from(table_name) {
+        for (i, row) in rows.iter().enumerate() {
+            if i > 0 {
+                preview.push_str(" | ");
+            }
+            let cells: Vec<String> = row.iter().map(|cell| cell.to_string()).collect();
+            preview.push_str(&cells.join(" "));
+        }
+    }
+    preview.chars().take(80).collect()
+}
+
+// fill the temp table with one row per non-empty register
+pub(crate) fn list_registers(
+    db: &mut Db,
+    list_table_name: &str,
+    clipboard_table_name: &str,
+) -> Result<()> {
+    let mut entries = vec![];
+    if db.exists(clipboard_table_name) {
+        entries.push(("\"".to_string(), preview(db, clipboard_table_name)));
+    }
+    for register in ('1'..='9').chain('a'..='z') {
+        let name = table_name(register);
+        if db.exists(&name) {
+            entries.push((format!("\"{}", register), preview(db, &name)));
+        }
+    }
+    db.create_or_replace_table(list_table_name)?;
+    db.create_column(list_table_name, "register")?;
+    db.create_column(list_table_name, "content")?;
+    for (register, content) in entries {
+        db.insert(list_table_name, vec![&register, &content])?;
+    }
+    Ok(())
+}
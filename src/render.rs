@@ -7,39 +7,143 @@ use termion::cursor::Goto;
 use termion::raw::IntoRawMode;
 #[allow(unused_imports)]
 use termion::raw::RawTerminal;
+use termion::style;
 
 use rzdb::{Data, Db};
 
-use crate::common::{self, is_cell};
+use crate::aggregate;
+use crate::calendar;
+use crate::checkbox::{self, CheckStyle};
+use crate::column_type::{self, ColumnType};
+use crate::common::{self, is_cell, Rect};
+use crate::complete;
+use crate::date::Ymd;
 use crate::editor::Editor;
+use crate::format;
+use crate::highlight;
+use crate::join;
 use crate::mode::Mode;
+use crate::notes;
+use crate::outline::{self, Folds};
 use crate::pos::Pos;
+use crate::text::{self, pad};
 use crate::State;
 
+// screen geometry of the last render, used to map mouse positions to cells
+pub(crate) struct Layout {
+    pub margin_left: usize,
+    pub margin_top: usize,
+    pub column_pos: Vec<usize>,
+    pub offset: Pos,
+    pub rows: Vec<usize>, // row shown on each screen line, folded rows are left out
+    pub row_lines: usize, // screen lines for rows, below them are footer and status line
+}
+
+impl Layout {
+    // x/y are the 1-indexed terminal coordinates reported by termion,
+    // None outside of the column header and the rows
+    pub fn cell_at(&self, x: u16, y: u16) -> Option<Pos> {
+        let (x, y) = (x as usize, y as usize);
+        if y <= self.margin_top || y > self.margin_top + 1 + self.row_lines {
+            return None;
+        }
+        let cell_y = if y == self.margin_top + 1 {
+            0
+        } else {
+            // empty lines below the last shown row continue from it
+            let idx = y - self.margin_top - 2 + self.offset.y;
+            match self.rows.get(idx) {
+                Some(row) => *row,
+                None => self.rows.last().copied().unwrap_or(0) + idx + 1 - self.rows.len(),
+            }
+        };
+        let left = *self
+            .column_pos
+            .get(self.offset.x.min(self.column_pos.len().checked_sub(1)?))?;
+        let mut cell_x = self.offset.x + 1;
+        if x >= self.margin_left {
+            let screen_x = x - self.margin_left + left;
+            cell_x = self.column_pos.len();
+            for (idx, pos) in self.column_pos.iter().enumerate().skip(1) {
+                if screen_x < *pos {
+                    cell_x = idx;
+                    break;
+                }
+            }
+        }
+        Some(Pos::new(cell_x.max(1), cell_y))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn render(
     db: &Db,
     state: &State,
     cursor: &Pos,
+    selection: &Option<Pos>,
+    folds: &Folds,
     mode: &Mode,
     editor: &Editor,
     message: &str,
-) {
+) -> Layout {
     let mut stdout = stdout().into_raw_mode().unwrap();
 
-    let pad = |s: &str, width: usize| {
-        let mut s = s.to_string();
-        while (s.chars().count()) < width {
-            s.push(' ');
-        }
-        s
-    };
-
     let margin_left = 6; // room for row id
     let margin_top: usize = 0; // nothing for now
-    let margin_bottom: usize = 2; // room for status line+command line
     let terminal_width = termion::terminal_size().unwrap().0 as usize;
     let terminal_height = termion::terminal_size().unwrap().1 as usize;
-    let table_content = db.select_from(&state.table_name).unwrap();
+    let table_content = db.select_from(&state.table_name).unwrap_or_default();
+    let column_cells = |idx: usize| {
+        table_content
+            .iter()
+            .map(|row| row.select_at(idx).unwrap_or(Data::Empty))
+            .collect::<Vec<_>>()
+    };
+    let column_aggregates = aggregate::get_all(db, &state.table_name);
+    let has_footer = column_aggregates.iter().any(|a| a.is_some());
+    let margin_bottom: usize = if has_footer { 3 } else { 2 }; // room for footer+status line+command line
+    let column_types = column_type::get_all(db, &state.table_name);
+    let column_formats = format::get_all(db, &state.table_name);
+    // joined columns show the display value of the referenced row
+    let join_values = join::get_all(db, &state.table_name)
+        .iter()
+        .map(|join| join.as_ref().map(|join| join.display_values(db)))
+        .collect::<Vec<_>>();
+    let join_value = |data: &Data, idx: usize| match join_values.get(idx) {
+        Some(Some(values)) => values.get(&data.to_string()),
+        _ => None,
+    };
+    let display = |data: &Data, idx: usize| {
+        if let Some(value) = join_value(data, idx) {
+            return value.clone();
+        }
+        if let Some(Some(ColumnType::Bool)) = column_types.get(idx) {
+            if let Some(checkbox) = checkbox::display(data) {
+                return checkbox;
+            }
+        }
+        format::display(data, column_formats.get(idx).and_then(|f| f.as_ref()))
+    };
+    let cell_notes = notes::get_all(db, &state.table_name);
+    let highlights = highlight::get_all(db, &state.table_name);
+    // checked rows are struck through or dimmed
+    let check_style = checkbox::get_style(db, &state.table_name);
+    let done_x = checkbox::bool_column(&column_types, None);
+    let footer = column_aggregates
+        .iter()
+        .enumerate()
+        .map(|(idx, aggregate)| match aggregate {
+            Some(aggregate) => format!(
+                "{} {}",
+                aggregate.name(),
+                aggregate.compute(
+                    &column_cells(idx),
+                    column_formats.get(idx).and_then(|f| f.as_ref())
+                )
+            ),
+            None => "".to_string(),
+        })
+        .collect::<Vec<_>>();
     let mut column_names_extended =
         common::get_column_names_extended(db, &state.table_name, cursor.x - 1);
     for (idx, column_name) in &mut column_names_extended.iter_mut().enumerate() {
@@ -65,8 +169,9 @@ pub(crate) fn render(
             mode,
             column_names_extended[cursor.x - 1]
         )
-    } else if is_cell(db, state, cursor.x - 1, cursor.y - 1) {
-        let cell = table_content[cursor.y - 1].select_at(cursor.x - 1).unwrap();
+    } else if let Some(Ok(cell)) = is_cell(db, state, cursor.x - 1, cursor.y - 1)
+        .then(|| table_content[cursor.y - 1].select_at(cursor.x - 1))
+    {
         let data_type_string = match cell {
             Data::Int(_) => "int",
             Data::Float(_) => "float",
@@ -76,9 +181,19 @@ pub(crate) fn render(
             Data::Join(_) => "join",
             Data::Empty => "empty",
         };
+        let declared_type = match column_types.get(cursor.x - 1) {
+            Some(Some(column_type)) => format!(" (column type {})", column_type.to_setting()),
+            _ => "".to_string(),
+        };
         format!(
-            "Table: {}, Cur: ({},{}), {}, {}:{}",
-            state.table_name, cursor.x, cursor.y, mode, data_type_string, cell
+            "Table: {}, Cur: ({},{}), {}, {}:{}{}",
+            state.table_name,
+            cursor.x,
+            cursor.y,
+            mode,
+            data_type_string,
+            format::first_line(cell.to_string()),
+            declared_type
         )
     } else {
         format!(
@@ -86,7 +201,30 @@ pub(crate) fn render(
             state.table_name, cursor.x, cursor.y, mode
         )
     };
-    let line = line.chars().take(terminal_width).collect::<String>();
+    // statistics of the selected cells
+    let line = match selection {
+        Some(anchor) => {
+            let r = Rect::from_corners(anchor, cursor);
+            let mut cells = vec![];
+            for y in r.start_y.max(1)..r.end_y {
+                for x in r.start_x..r.end_x {
+                    if let Some(Ok(cell)) = is_cell(db, state, x - 1, y - 1)
+                        .then(|| table_content[y - 1].select_at(x - 1))
+                    {
+                        cells.push(cell);
+                    }
+                }
+            }
+            format!("{} | {}", line, aggregate::selection_stats(&cells))
+        }
+        None => line,
+    };
+    // note of the cell under the cursor
+    let line = match cell_notes.get(cursor) {
+        Some(note) => format!("{} | note: {}", line, note),
+        None => line,
+    };
+    let line = text::truncate(&line, terminal_width);
     out += &format!(
         "{}{}{}{}{}",
         Fg(Black),
@@ -98,21 +236,25 @@ pub(crate) fn render(
 
     // get the max width of each column
     let mut column_widths: Vec<usize> = vec![];
-    for column_name in &column_names_extended {
-        column_widths.push(column_name.chars().count());
+    for (idx, column_name) in column_names_extended.iter().enumerate() {
+        let footer_len = footer.get(idx).map_or(0, |s| text::width(s));
+        column_widths.push(text::width(column_name).max(footer_len));
     }
     for row in &table_content {
         for (idx, column) in row.iter().enumerate() {
-            let len = column.no_time_seconds().chars().count();
+            let len = text::width(&display(column, idx));
             if len > column_widths[idx] {
                 column_widths[idx] = len;
             }
         }
     }
 
+    // long text is edited in a popup window below the cell
+    let popup_editor = *mode == Mode::Insert && editor.is_long();
+
     // length of editor field while editing
-    if *mode == Mode::Insert {
-        let len = editor.len_utf8();
+    if (*mode == Mode::Insert || *mode == Mode::Calendar) && !popup_editor {
+        let len = editor.width();
         if len > column_widths[cursor.x - 1] {
             column_widths[cursor.x - 1] = len;
         }
@@ -135,8 +277,18 @@ pub(crate) fn render(
         }
         offset.x += 1;
     }
-    if cursor.y > terminal_height - margin_top - margin_bottom - 2 {
-        offset.y = cursor.y - (terminal_height - margin_top - margin_bottom - 2);
+    // rows shown on screen, without folded subtrees, and the empty rows up to the cursor
+    let folded_rows = folds.rows(db, &state.table_name);
+    let mut rows = outline::visible(db, &state.table_name, &folded_rows);
+    rows.extend(table_content.len() + 1..=cursor.y);
+    let outline_texts = outline::outline_texts(db, &state.table_name);
+    // screen line of the cursor, 0 is the column header
+    let cursor_line = rows
+        .iter()
+        .position(|y| *y == cursor.y)
+        .map_or(cursor.y, |idx| idx + 1);
+    if cursor_line > terminal_height - margin_top - margin_bottom - 2 {
+        offset.y = cursor_line - (terminal_height - margin_top - margin_bottom - 2);
     }
 
     // column headers
@@ -146,10 +298,10 @@ pub(crate) fn render(
         let column_name = &column_names_extended[idx];
         line += &pad(column_name, column_widths[idx] + 1);
     }
-    if line.chars().count() > terminal_width - margin_left {
-        line = line.chars().take(terminal_width).collect::<String>();
+    if text::width(&line) > terminal_width - margin_left {
+        line = text::truncate(&line, terminal_width);
     } else {
-        line += &" ".repeat(terminal_width - line.chars().count());
+        line = pad(&line, terminal_width);
     }
     out += &format!(
         "{}{}{}{}{}{}",
@@ -176,61 +328,153 @@ pub(crate) fn render(
     }
 
     // rows
-    #[allow(clippy::manual_clamp)]
-    let num_rows = table_content.len().max(cursor.y).min(terminal_height);
-    let last_row = num_rows.min(terminal_height - margin_top - margin_bottom - 1);
-    for idx_y in 0..last_row {
-        // row id
+    let selection_rect = selection
+        .as_ref()
+        .map(|anchor| Rect::from_corners(anchor, cursor));
+    let last_row = terminal_height - margin_top - margin_bottom - 1;
+    for (idx_y, row_y) in rows
+        .iter()
+        .copied()
+        .skip(offset.y)
+        .take(last_row)
+        .enumerate()
+    {
+        // row id, and a fold marker for rows with children in the outline
+        let fold_marker = match &outline_texts {
+            Some(texts) if outline::has_children(texts, row_y) => {
+                if folded_rows.contains(&row_y) {
+                    "▸"
+                } else {
+                    "▾"
+                }
+            }
+            _ => " ",
+        };
         out += &format!(
-            "{}{}{:4}{}",
+            "{}{}{:4}{}{}",
             Fg(Red),
             Goto(1, (margin_top + idx_y + 2) as u16),
-            idx_y + offset.y + 1,
+            row_y,
+            fold_marker,
             Fg(Reset)
         );
         // columns
-        if idx_y + offset.y < table_content.len() {
-            let row = &table_content[idx_y + offset.y];
+        if row_y <= table_content.len() {
+            let row = &table_content[row_y - 1];
+            let is_done = done_x.is_some_and(|x| {
+                row.select_at(x)
+                    .is_ok_and(|data| checkbox::is_checked(&data))
+            });
+            let (row_style, row_style_reset) = match check_style {
+                Some(CheckStyle::Strike) if is_done => (
+                    format!("{}", style::CrossedOut),
+                    format!("{}", style::NoCrossedOut),
+                ),
+                Some(CheckStyle::Dim) if is_done => {
+                    (format!("{}", style::Faint), format!("{}", style::NoFaint))
+                }
+                _ => ("".to_string(), "".to_string()),
+            };
             for idx_x in offset.x..num_columns {
-                let data = if idx_x < row.len() {
-                    row.select_at(idx_x).unwrap().no_time_seconds()
+                let cell = if idx_x < row.len() {
+                    row.select_at(idx_x).unwrap_or(Data::Empty)
                 } else {
-                    "".to_string()
+                    Data::Empty
                 };
+                let data = display(&cell, idx_x);
+                // cells that don't fit the declared column type are shown in red
+                let is_invalid = column_types
+                    .get(idx_x)
+                    .and_then(|column_type| column_type.as_ref())
+                    .is_some_and(|column_type| !column_type.conforms(&cell))
+                    || (!matches!(cell, Data::Empty)
+                        && matches!(join_values.get(idx_x), Some(Some(_)))
+                        && join_value(&cell, idx_x).is_none());
 
-                // render the cursor in inverse
-                if idx_x == cursor.x - 1 && idx_y + offset.y + 1 == cursor.y {
-                    out += &format!("{}{}", Fg(Black), Bg(White));
-                }
-
-                // check if beyond right edge of window
+                // check if beyond right edge of window, before any style is started
                 if column_pos[idx_x] - column_pos[offset.x] + margin_left > terminal_width {
                     break;
                 }
 
+                // conditional formats first, so that cursor and selection show over them
+                let cell_style = highlight::style(
+                    &highlights,
+                    |x| row.select_at(x).unwrap_or(Data::Empty),
+                    idx_x,
+                );
+                out += &cell_style.start();
+
+                // render the cursor in inverse, the selection in cyan
+                let is_cursor = idx_x == cursor.x - 1 && row_y == cursor.y;
+                let is_selected = selection_rect
+                    .as_ref()
+                    .is_some_and(|r| r.contains(idx_x + 1, row_y));
+                if is_cursor {
+                    out += &format!("{}{}", Fg(Black), Bg(White));
+                } else if is_selected {
+                    out += &format!("{}{}", Fg(Black), Bg(Cyan));
+                }
+                if is_invalid {
+                    out += &format!("{}", Fg(Red));
+                }
+
                 // don't display data if it is too long
                 let width_left =
                     terminal_width + column_pos[offset.x] - column_pos[idx_x] + 1 - margin_left;
-                let data = data.chars().take(width_left).collect::<String>();
+                let data = text::truncate(&data, width_left);
+                // numbers are right aligned
+                let data = if format::is_numeric(&cell) {
+                    let width = column_widths[idx_x].min(width_left);
+                    " ".repeat(width.saturating_sub(text::width(&data))) + &data
+                } else {
+                    data
+                };
 
                 out += &format!(
-                    "{}{}",
+                    "{}{}{}{}",
                     Goto(
                         (column_pos[idx_x] - column_pos[offset.x] + margin_left) as u16,
                         (margin_top + idx_y + 2) as u16
                     ),
+                    row_style,
                     pad(&data, column_widths[idx_x] + 1),
+                    row_style_reset,
                 );
-                if idx_x == cursor.x - 1 && idx_y + offset.y + 1 == cursor.y {
+                if is_cursor || is_selected || is_invalid {
                     out += &format!("{}{}", Fg(Reset), Bg(Reset));
                 }
+                out += &cell_style.end();
             }
         }
     }
 
+    // footer with the aggregate of each column
+    if has_footer {
+        let mut line = "   Σ ".to_string();
+        for (idx, width) in column_widths
+            .iter()
+            .enumerate()
+            .take(num_columns)
+            .skip(offset.x)
+        {
+            let text = footer.get(idx).map_or("", |s| s.as_str());
+            line += &pad(text, width + 1);
+        }
+        let line = text::truncate(&line, terminal_width);
+        out += &format!(
+            "{}{}{}{}{}",
+            Goto(1, terminal_height as u16 - 2),
+            Fg(Yellow),
+            pad(&line, terminal_width),
+            Fg(Reset),
+            Bg(Reset),
+        );
+    }
+
     // render editor cell / cursor if outside existing cells
     if *mode == Mode::Insert
         || *mode == Mode::Command
+        || *mode == Mode::Calendar
         || cursor.y > table_content.len()
         || (cursor.y > 0 && cursor.x > table_content[0].len())
     {
@@ -239,18 +483,24 @@ pub(crate) fn render(
         } else {
             (
                 (column_pos[cursor.x - 1] + margin_left - column_pos[offset.x]) as u16,
-                (margin_top + cursor.y - offset.y + 1) as u16,
+                (margin_top + cursor_line - offset.y + 1) as u16,
                 column_widths[cursor.x - 1],
                 "",
             )
         };
 
-        let (line, bg) = if *mode == Mode::Insert || *mode == Mode::Command {
+        let (line, bg) = if popup_editor {
+            let line = format::first_line(editor.get_line());
+            (
+                pad(&text::truncate(&line, cursor_len), cursor_len),
+                format!("{}", Bg(Yellow)),
+            )
+        } else if *mode == Mode::Insert || *mode == Mode::Command || *mode == Mode::Calendar {
             (
                 format!(
                     "{}{}",
                     prefix,
-                    pad(&editor.line, cursor_len - prefix.chars().count())
+                    pad(&editor.line, cursor_len - text::width(prefix))
                 ),
                 format!("{}", Bg(Yellow)),
             )
@@ -269,18 +519,14 @@ pub(crate) fn render(
         );
 
         // render cursor of editor
-        if *mode == Mode::Insert || *mode == Mode::Command {
-            let ch = if editor.cur_x >= editor.line.chars().count() {
-                " ".to_string()
-            } else {
-                editor.line.chars().nth(editor.cur_x).unwrap().to_string()
-            };
+        if (*mode == Mode::Insert || *mode == Mode::Command) && !popup_editor {
+            let ch = editor.cursor_grapheme();
             out += &format!(
                 "{}{}{}{}{}{}",
                 Bg(Blue),
                 Fg(Black),
                 Goto(
-                    x_pos + editor.cur_x as u16 + prefix.chars().count() as u16,
+                    x_pos + (editor.cursor_width() + text::width(prefix)) as u16,
                     y_pos
                 ),
                 ch,
@@ -290,7 +536,157 @@ pub(crate) fn render(
         }
     }
 
-    if *mode == Mode::Error {
+    // popups open below the edited cell, or above it if there is no room below
+    let popup_origin = |width: usize, height: usize| {
+        let x = column_pos[cursor.x - 1] + margin_left - column_pos[offset.x];
+        let x = x.min(terminal_width.saturating_sub(width) + 1);
+        let y = margin_top + cursor_line - offset.y + 2;
+        let y = if y + height > terminal_height - margin_bottom + 1 {
+            (margin_top + cursor_line - offset.y)
+                .saturating_sub(height)
+                .max(1)
+        } else {
+            y
+        };
+        (x, y)
+    };
+
+    // popup editor, scrolled to keep the cursor visible
+    if popup_editor {
+        let width = (terminal_width / 2)
+            .max(column_widths[cursor.x - 1])
+            .min(terminal_width - 1);
+        let (lines, (cursor_y, cursor_x)) = editor.wrapped(width);
+        let max_lines = (terminal_height - margin_bottom).saturating_sub(3).max(1);
+        let first = (cursor_y + 1).saturating_sub(max_lines);
+        let height = lines.len().min(max_lines);
+        let (x, y) = popup_origin(width + 1, height);
+        for (line_y, line) in lines.iter().enumerate().skip(first).take(height) {
+            out += &format!(
+                "{}{}{}{}",
+                Goto(x as u16, (y + line_y - first) as u16),
+                Fg(Black),
+                Bg(Yellow),
+                pad(line, width + 1),
+            );
+            if line_y == cursor_y {
+                let ch = editor.cursor_grapheme();
+                out += &format!(
+                    "{}{}{}{}",
+                    Goto((x + cursor_x) as u16, (y + line_y - first) as u16),
+                    Bg(Blue),
+                    ch,
+                    Bg(Yellow),
+                );
+            }
+            out += &format!("{}{}", Fg(Reset), Bg(Reset));
+        }
+    }
+
+    // join picker or completions below the edited cell
+    if *mode == Mode::Insert && !popup_editor {
+        let choices = join::picker(db, state, cursor, &editor.line);
+        let (choices, picked) = if choices.is_empty() {
+            let completions = complete::candidates(db, state, cursor, &editor.line);
+            let choices = completions
+                .into_iter()
+                .map(|value| (value.clone(), value))
+                .collect::<Vec<_>>();
+            (choices, editor.picked)
+        } else {
+            (choices, editor.highlighted())
+        };
+        let max_lines = 8;
+        let first = picked.map_or(0, |idx| (idx + 1).saturating_sub(max_lines));
+        let lines = choices
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(max_lines)
+            .map(|(idx, (key, value))| {
+                let choice = if key == value {
+                    key.clone()
+                } else {
+                    format!("{}  {}", key, value)
+                };
+                (idx, choice)
+            })
+            .collect::<Vec<_>>();
+        let width = lines
+            .iter()
+            .map(|(_, choice)| text::width(choice))
+            .max()
+            .unwrap_or(0)
+            .min(terminal_width);
+        let (x, y) = popup_origin(width, lines.len());
+        for (line_y, (idx, choice)) in lines.iter().enumerate() {
+            let bg = if Some(*idx) == picked {
+                format!("{}", Bg(Cyan))
+            } else {
+                format!("{}", Bg(White))
+            };
+            out += &format!(
+                "{}{}{}{}{}{}",
+                Goto(x as u16, (y + line_y) as u16),
+                Fg(Black),
+                bg,
+                pad(&text::truncate(choice, width), width),
+                Fg(Reset),
+                Bg(Reset),
+            );
+        }
+    }
+
+    // month calendar of the highlighted date, today in red, dates of the column in blue
+    if *mode == Mode::Calendar {
+        let selected = calendar::selected(editor);
+        let today = Ymd::today();
+        let column_dates = calendar::column_dates(db, &state.table_name, cursor.x - 1);
+        let weeks = calendar::month_grid(selected.year, selected.month);
+        let width = 7 * 3 + 1;
+        let (x, y) = popup_origin(width, weeks.len() + 2);
+        let title = selected.format("%B %Y");
+        let mut lines = vec![
+            format!("{:^width$}", title, width = width),
+            " Mo Tu We Th Fr Sa Su ".to_string(),
+        ];
+        for week in &weeks {
+            let mut line = String::new();
+            for date in week {
+                let Some(date) = date else {
+                    line += "   ";
+                    continue;
+                };
+                let fg = if *date == today {
+                    format!("{}", Fg(Red))
+                } else if column_dates.contains(date) {
+                    format!("{}", Fg(Blue))
+                } else {
+                    format!("{}", Fg(Black))
+                };
+                let bg = if *date == selected {
+                    format!("{}", Bg(Cyan))
+                } else {
+                    format!("{}", Bg(White))
+                };
+                line += &format!(" {}{}{:>2}{}{}", fg, bg, date.day, Fg(Black), Bg(White));
+            }
+            lines.push(line + " ");
+        }
+        for (line_y, line) in lines.iter().enumerate() {
+            out += &format!(
+                "{}{}{}{}{}{}",
+                Goto(x as u16, (y + line_y) as u16),
+                Fg(Black),
+                Bg(White),
+                line,
+                Fg(Reset),
+                Bg(Reset),
+            );
+        }
+    }
+
+    if *mode == Mode::Error || (*mode == Mode::Insert && !message.is_empty()) {
         out += &format!(
             "{}{}{}{}{}{}",
             Bg(Red),
@@ -304,26 +700,36 @@ pub(crate) fn render(
 
     // black border horizontally around each cell
     //out += &format!("{}", Bg(Black));
-    let last_row = table_content
-        .len()
-        .min(terminal_height - margin_top - margin_bottom - 1);
     let last_column = if table_content.is_empty() {
         0
     } else {
         table_content[0].len()
     };
-    for idx_y in 2..=(last_row + 1) {
-        for idx_x in (offset.x + 1)..last_column {
+    let table_rows = rows
+        .iter()
+        .skip(offset.y)
+        .take(last_row)
+        .enumerate()
+        .filter(|(_, row_y)| **row_y <= table_content.len());
+    // cells with a note get a red corner instead
+    for (idx_y, row_y) in table_rows {
+        for idx_x in (offset.x + 1)..=last_column {
             let x = column_pos[idx_x] - column_pos[offset.x] + margin_left - 1;
             if x > terminal_width {
                 break;
             }
             let col_pos = Pos {
                 x: idx_x,
-                y: idx_y + offset.y - 1,
+                y: *row_y,
             };
-            if *mode == Mode::Normal || col_pos != *cursor {
-                out += &format!("{}·", Goto(x as u16, idx_y as u16),);
+            if *mode != Mode::Normal && col_pos == *cursor {
+                continue;
+            }
+            let goto = Goto(x as u16, (margin_top + idx_y + 2) as u16);
+            if cell_notes.contains_key(&col_pos) {
+                out += &format!("{}{}◥{}", goto, Fg(Red), Fg(Reset));
+            } else if idx_x < last_column {
+                out += &format!("{}·", goto);
             }
         }
     }
@@ -339,6 +745,15 @@ pub(crate) fn render(
     // output everything
     write!(stdout, "{}", out).unwrap();
     stdout.flush().unwrap();
+
+    Layout {
+        margin_left,
+        margin_top,
+        column_pos,
+        offset,
+        rows,
+        row_lines: last_row,
+    }
 }
 
 pub fn cleanup() {
@@ -0,0 +1,155 @@
+use rzdb::{Data, Db};
+
+use crate::error::Result;
+
+// every row gets an id that stays with it when rows are inserted, deleted, moved or sorted,
+// so that notes and folds keep pointing at their row. the ids of a table are stored in row
+// order next to the tables like the settings, with the next free id so that ids of deleted
+// rows aren't handed out again.
+pub(crate) const ROW_IDS_TABLE: &str = ".rowids";
+const ROW_IDS_COLUMNS: [&str; 3] = ["table_name", "next_id", "ids"];
+
+pub(crate) type RowId = u64;
+
+fn cell(db: &Db, x: usize, y: usize) -> String {
+    db.select_at(ROW_IDS_TABLE, x, y)
+        .map(|data| data.to_string())
+        .unwrap_or_default()
+}
+
+fn find(db: &Db, table_name: &str) -> Option<usize> {
+    if !db.exists(ROW_IDS_TABLE) {
+        return None;
+    }
+    (0..db.get_row_count(ROW_IDS_TABLE).unwrap_or(0)).find(|&y| cell(db, 0, y) == table_name)
+}
+
+// ids and next free id as stored, rows added since don't have an id yet
+fn stored(db: &Db, table_name: &str) -> (Vec<RowId>, RowId) {
+    match find(db, table_name) {
+        Some(y) => {
+            let ids: Vec<RowId> = cell(db, 2, y)
+                .split_whitespace()
+                .filter_map(|id| id.parse().ok())
+                .collect();
+            let next_id = cell(db, 1, y).parse().unwrap_or(1);
+            let next_id = ids.iter().map(|id| id + 1).fold(next_id, RowId::max);
+            (ids, next_id)
+        }
+        None => (vec![], 1),
+    }
+}
+
+fn store(db: &mut Db, table_name: &str, ids: &[RowId], next_id: RowId) -> Result<()> {
+    if !db.exists(ROW_IDS_TABLE) {
+        db.create_table(ROW_IDS_TABLE)?;
+        for column in ROW_IDS_COLUMNS {
+            db.create_column(ROW_IDS_TABLE, column)?;
+        }
+    }
+    let y = match find(db, table_name) {
+        Some(y) => y,
+        None => {
+            let y = db.get_row_count(ROW_IDS_TABLE)?;
+            db.insert(ROW_IDS_TABLE, vec![""; ROW_IDS_COLUMNS.len()])?;
+            db.set_at(ROW_IDS_TABLE, y, 0, Data::String(table_name.to_string()))?;
+            y
+        }
+    };
+    let ids = ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    db.set_at(ROW_IDS_TABLE, y, 1, Data::String(next_id.to_string()))?;
+    db.set_at(ROW_IDS_TABLE, y, 2, Data::String(ids))?;
+    Ok(())
+}
+
+// stored ids, with new ids for the first len rows that don't have one
+fn padded(db: &Db, table_name: &str, len: usize) -> (Vec<RowId>, RowId) {
+    let (mut ids, mut next_id) = stored(db, table_name);
+    while ids.len() < len {
+        ids.push(next_id);
+        next_id += 1;
+    }
+    (ids, next_id)
+}
+
+// ids of all rows, rows without one get a new id
+pub(crate) fn sync(db: &mut Db, table_name: &str) -> Result<Vec<RowId>> {
+    let row_count = db.get_row_count(table_name)?;
+    let (mut ids, next_id) = padded(db, table_name, row_count);
+    ids.truncate(row_count);
+    if stored(db, table_name) != (ids.clone(), next_id) {
+        store(db, table_name, &ids, next_id)?;
+    }
+    Ok(ids)
+}
+
+// id of row y, 1-indexed like the cursor
+pub(crate) fn get(db: &mut Db, table_name: &str, y: usize) -> Result<RowId> {
+    let ids = sync(db, table_name)?;
+    y.checked_sub(1)
+        .and_then(|idx| ids.get(idx).copied())
+        .ok_or_else(|| "No row here".into())
+}
+
+// id of every row without handing out new ids, for drawing
+pub(crate) fn get_all(db: &Db, table_name: &str) -> Vec<Option<RowId>> {
+    let (ids, _) = stored(db, table_name);
+    (0..db.get_row_count(table_name).unwrap_or(0))
+        .map(|idx| ids.get(idx).copied())
+        .collect()
+}
+
+// the following keep the ids in step with the rows, all indexes are 0-indexed
+
+// count rows were inserted at y
+pub(crate) fn insert_at(db: &mut Db, table_name: &str, y: usize, count: usize) -> Result<()> {
+    let (mut ids, next_id) = padded(db, table_name, y);
+    let new_ids = next_id..next_id + count as RowId;
+    ids.splice(y..y, new_ids);
+    store(db, table_name, &ids, next_id + count as RowId)
+}
+
+pub(crate) fn delete_at(db: &mut Db, table_name: &str, y: usize) -> Result<()> {
+    let (mut ids, next_id) = stored(db, table_name);
+    if y < ids.len() {
+        ids.remove(y);
+        store(db, table_name, &ids, next_id)?;
+    }
+    Ok(())
+}
+
+// rows [start, mid) and [mid, end) traded places
+pub(crate) fn rotate(
+    db: &mut Db,
+    table_name: &str,
+    start: usize,
+    mid: usize,
+    end: usize,
+) -> Result<()> {
+    let (mut ids, next_id) = padded(db, table_name, end);
+    ids[start..end].rotate_left(mid - start);
+    store(db, table_name, &ids, next_id)
+}
+
+// the rows were reordered, row y now holds what was in row order[y]
+pub(crate) fn reorder(db: &mut Db, table_name: &str, order: &[usize]) -> Result<()> {
+    let (ids, next_id) = padded(db, table_name, order.len());
+    let mut new_ids: Vec<RowId> = order.iter().map(|y| ids[*y]).collect();
+    new_ids.extend_from_slice(&ids[order.len()..]);
+    store(db, table_name, &new_ids, next_id)
+}
+
+// the rows of a dropped table are gone, but their ids stay used
+pub(crate) fn drop_table(db: &mut Db, table_name: &str) -> Result<()> {
+    match find(db, table_name) {
+        Some(_) => {
+            let (_, next_id) = stored(db, table_name);
+            store(db, table_name, &[], next_id)
+        }
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,115 @@
+use rzdb::{Data, Db};
+
+use crate::error::Result;
+
+// per table and per column settings, stored in the database next to the tables.
+// table wide settings use an empty column name.
+pub(crate) const SETTINGS_TABLE: &str = ".settings";
+const SETTINGS_COLUMNS: [&str; 4] = ["table_name", "column_name", "key", "value"];
+
+fn cell(db: &Db, x: usize, y: usize) -> String {
+    db.select_at(SETTINGS_TABLE, x, y)
+        .map(|data| data.to_string())
+        .unwrap_or_default()
+}
+
+// row index and value of a setting
+fn find(db: &Db, table_name: &str, column_name: &str, key: &str) -> Option<(usize, String)> {
+    if !db.exists(SETTINGS_TABLE) {
+        return None;
+    }
+    let row_count = db.get_row_count(SETTINGS_TABLE).unwrap_or(0);
+    (0..row_count)
+        .find(|&y| {
+            cell(db, 0, y) == table_name && cell(db, 1, y) == column_name && cell(db, 2, y) == key
+        })
+        .map(|y| (y, cell(db, 3, y)))
+}
+
+pub(crate) fn get(db: &Db, table_name: &str, column_name: &str, key: &str) -> Option<String> {
+    find(db, table_name, column_name, key).map(|(_, value)| value)
+}
+
+// an empty value removes the setting
+pub(crate) fn set(
+    db: &mut Db,
+    table_name: &str,
+    column_name: &str,
+    key: &str,
+    value: &str,
+) -> Result<()> {
+    if !db.exists(SETTINGS_TABLE) {
+        db.create_table(SETTINGS_TABLE)?;
+        for column in SETTINGS_COLUMNS {
+            db.create_column(SETTINGS_TABLE, column)?;
+        }
+    }
+    match find(db, table_name, column_name, key) {
+        Some((y, _)) if value.is_empty() => db.delete_row_at(SETTINGS_TABLE, y)?,
+        Some((y, _)) => db.set_at(SETTINGS_TABLE, y, 3, Data::String(value.to_string()))?,
+        None if value.is_empty() => {}
+        None => {
+            let y = db.get_row_count(SETTINGS_TABLE)?;
+            db.insert(SETTINGS_TABLE, vec![""; SETTINGS_COLUMNS.len()])?;
+            for (x, text) in [table_name, column_name, key, value].iter().enumerate() {
+                db.set_at(SETTINGS_TABLE, y, x, Data::String(text.to_string()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// settings follow their column when it's renamed
+pub(crate) fn rename_column(
+    db: &mut Db,
+    table_name: &str,
+    old_column_name: &str,
+    new_column_name: &str,
+) -> Result<()> {
+    if !db.exists(SETTINGS_TABLE) {
+        return Ok(());
+    }
+    for y in 0..db.get_row_count(SETTINGS_TABLE)? {
+        if cell(db, 0, y) == table_name && cell(db, 1, y) == old_column_name {
+            db.set_at(
+                SETTINGS_TABLE,
+                y,
+                1,
+                Data::String(new_column_name.to_string()),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// settings of deleted columns and tables go with them
+fn remove_where(db: &mut Db, matches: impl Fn(&Db, usize) -> bool) -> Result<()> {
+    if !db.exists(SETTINGS_TABLE) {
+        return Ok(());
+    }
+    for y in (0..db.get_row_count(SETTINGS_TABLE)?).rev() {
+        if matches(db, y) {
+            db.delete_row_at(SETTINGS_TABLE, y)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn remove_column(db: &mut Db, table_name: &str, column_name: &str) -> Result<()> {
+    remove_where(db, |db, y| {
+        cell(db, 0, y) == table_name && cell(db, 1, y) == column_name
+    })
+}
+
+pub(crate) fn remove_table(db: &mut Db, table_name: &str) -> Result<()> {
+    remove_where(db, |db, y| cell(db, 0, y) == table_name)
+}
+
+// value of a setting for every column of a table, in column order
+pub(crate) fn get_for_columns(db: &Db, table_name: &str, key: &str) -> Vec<Option<String>> {
+    db.get_column_names(table_name)
+        .unwrap_or_default()
+        .iter()
+        .map(|column_name| get(db, table_name, column_name, key))
+        .collect()
+}
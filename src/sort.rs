@@ -0,0 +1,122 @@
+use std::cmp::Ordering;
+
+use rzdb::{Data, Db};
+
+use crate::column_type::{self, bool_to_string, parse_bool, ColumnType};
+use crate::date::{Hms, Ymd};
+use crate::error::Result;
+use crate::rowid;
+
+// numbers, then dates, then times, then text; booleans sort as text, so open before done
+fn rank(data: &Data) -> u8 {
+    match data {
+        Data::Int(_) | Data::Float(_) => 0,
+        Data::Date(_) => 1,
+        Data::Time(_) => 2,
+        _ => 3,
+    }
+}
+
+pub(crate) fn compare(a: &Data, b: &Data) -> Ordering {
+    let number = |data: &Data| data.to_string().parse::<f64>().unwrap_or(0.0);
+    match (a, b) {
+        (Data::Int(_) | Data::Float(_), Data::Int(_) | Data::Float(_)) => {
+            number(a).total_cmp(&number(b))
+        }
+        (Data::Date(_), Data::Date(_)) => Ymd::from_data(a).cmp(&Ymd::from_data(b)),
+        (Data::Time(_), Data::Time(_)) => Hms::from_data(a).cmp(&Hms::from_data(b)),
+        _ if rank(a) != rank(b) => rank(a).cmp(&rank(b)),
+        _ => a
+            .to_string()
+            .to_lowercase()
+            .cmp(&b.to_string().to_lowercase()),
+    }
+}
+
+// an unchecked checkbox is often just an empty cell, it sorts as false like [ ]
+fn bool_key(data: Data) -> Data {
+    match data {
+        Data::Empty => Data::String(bool_to_string(false)),
+        _ => match parse_bool(&data.to_string()) {
+            Some(b) => Data::String(bool_to_string(b)),
+            None => data,
+        },
+    }
+}
+
+// stable sort, rows with equal keys keep their order. empty cells stay at the end in both
+// directions.
+fn compare_keys(a: &Data, b: &Data, descending: bool) -> Ordering {
+    match (a, b) {
+        (Data::Empty, Data::Empty) => Ordering::Equal,
+        (Data::Empty, _) => Ordering::Greater,
+        (_, Data::Empty) => Ordering::Less,
+        (a, b) if descending => compare(b, a),
+        (a, b) => compare(a, b),
+    }
+}
+
+// sort all rows by column x
+pub(crate) fn sort_rows(db: &mut Db, table_name: &str, x: usize, descending: bool) -> Result<()> {
+    let is_bool = column_type::get_all(db, table_name).get(x) == Some(&Some(ColumnType::Bool));
+    // each row with its old index, so that the row ids can follow
+    let mut rows: Vec<(usize, Vec<Data>)> = db
+        .select_from(table_name)?
+        .iter()
+        .map(|row| {
+            (0..row.len())
+                .map(|x| row.select_at(x).unwrap_or(Data::Empty))
+                .collect()
+        })
+        .enumerate()
+        .collect();
+    let key = |(_, row): &(usize, Vec<Data>)| {
+        let data = row.get(x).cloned().unwrap_or(Data::Empty);
+        if is_bool {
+            bool_key(data)
+        } else {
+            data
+        }
+    };
+    rows.sort_by(|a, b| compare_keys(&key(a), &key(b), descending));
+    let order: Vec<usize> = rows.iter().map(|(y, _)| *y).collect();
+    for (y, (_, row)) in rows.into_iter().enumerate() {
+        for (x, data) in row.into_iter().enumerate() {
+            db.set_at(table_name, y, x, data)?;
+        }
+    }
+    rowid::reorder(db, table_name, &order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let parse = |s: &str| Data::parse(s);
+        assert_eq!(compare(&parse("9"), &parse("10")), Ordering::Less);
+        assert_eq!(compare(&parse("2.5"), &parse("2")), Ordering::Greater);
+        assert_eq!(compare(&parse("false"), &parse("true")), Ordering::Less);
+        assert_eq!(compare(&parse("apple"), &parse("Banana")), Ordering::Less);
+        assert_eq!(compare(&parse("10"), &parse("apple")), Ordering::Less);
+    }
+
+    fn parse_key(s: &str) -> Data {
+        bool_key(Data::parse(s))
+    }
+
+    #[test]
+    fn test_bool_keys() {
+        // a checkbox that was never toggled is empty and sorts with the open rows
+        let mut keys = [
+            parse_key("true"),
+            bool_key(Data::Empty),
+            parse_key("no"),
+            parse_key("x"),
+        ];
+        keys.sort_by(|a, b| compare_keys(a, b, false));
+        let texts: Vec<String> = keys.iter().map(|data| data.to_string()).collect();
+        assert_eq!(texts, ["false", "false", "true", "true"]);
+    }
+}
@@ -0,0 +1,48 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// text measured in terminal columns: wide characters take two, combining marks none
+
+pub(crate) fn width(s: &str) -> usize {
+    s.width()
+}
+
+// the longest start of s that fits into width columns, never splitting a grapheme
+pub(crate) fn truncate(s: &str, width: usize) -> String {
+    let mut out = String::new();
+    let mut used = 0;
+    for grapheme in s.graphemes(true) {
+        used += grapheme.width();
+        if used > width {
+            break;
+        }
+        out += grapheme;
+    }
+    out
+}
+
+// s filled up with spaces to width columns
+pub(crate) fn pad(s: &str, width: usize) -> String {
+    format!("{}{}", s, " ".repeat(width.saturating_sub(s.width())))
+}
+
+// cells or literals as strings, for comparing them in tests
+#[cfg(test)]
+pub(crate) fn strings<T: ToString>(v: &[T]) -> Vec<String> {
+    v.iter().map(|item| item.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_width() {
+        assert_eq!(width("abc"), 3);
+        assert_eq!(width("日本"), 4);
+        assert_eq!(width("e\u{301}"), 1);
+        assert_eq!(truncate("日本語", 5), "日本");
+        assert_eq!(truncate("cafe\u{301}s", 4), "cafe\u{301}");
+        assert_eq!(pad("日本", 5), "日本 ");
+    }
+}
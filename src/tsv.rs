@@ -0,0 +1,146 @@
+// Split text copied from a spreadsheet or a browser into rows and cells.
+// Tabs separate cells if there are any. Otherwise commas do, but only if every line of a
+// multi-line text has one, so that a sentence with a comma stays in one cell.
+// Cells may be quoted with ", doubled quotes inside are literal quotes. This works without
+// a delimiter too, so a single quoted cell may span several lines.
+pub(crate) fn parse(text: &str) -> Vec<Vec<String>> {
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    let text = text.strip_suffix('\r').unwrap_or(text);
+    let lines: Vec<&str> = text.lines().collect();
+    let delimiter = if text.contains('\t') {
+        Some('\t')
+    } else if lines.len() > 1 && lines.iter().all(|line| line.contains(',')) {
+        Some(',')
+    } else {
+        None
+    };
+    // a quote that is never closed was meant literally
+    split(text, delimiter, true)
+        .or_else(|| split(text, delimiter, false))
+        .unwrap_or_default()
+}
+
+// a cell while it is read. quotes followed by more text were part of the text,
+// e. g. "Hello" he said, then the cell is kept as written.
+#[derive(Default)]
+struct Cell {
+    text: String,
+    raw: String,
+    quoted: bool,
+    text_after_quotes: bool,
+}
+
+impl Cell {
+    fn push(&mut self, ch: char) {
+        self.text_after_quotes |= self.quoted;
+        self.text.push(ch);
+        self.raw.push(ch);
+    }
+
+    fn finish(self) -> String {
+        if self.text_after_quotes {
+            self.raw
+        } else {
+            self.text
+        }
+    }
+}
+
+// None if a quoted cell isn't closed
+fn split(text: &str, delimiter: Option<char>, quoting: bool) -> Option<Vec<Vec<String>>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut cell = Cell::default();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            cell.raw.push(ch);
+            if ch == '"' {
+                if chars.peek() == Some(&'"') {
+                    cell.text.push('"');
+                    cell.raw.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                cell.text.push(ch);
+            }
+        } else if quoting && ch == '"' && cell.raw.is_empty() {
+            in_quotes = true;
+            cell.quoted = true;
+            cell.raw.push(ch);
+        } else if Some(ch) == delimiter {
+            row.push(std::mem::take(&mut cell).finish());
+        } else if ch == '\n' {
+            row.push(std::mem::take(&mut cell).finish());
+            rows.push(std::mem::take(&mut row));
+        } else if ch != '\r' {
+            cell.push(ch);
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    if !text.is_empty() {
+        row.push(cell.finish());
+        rows.push(row);
+    }
+    Some(rows)
+}
+
+// inverse of parse(), tab separated
+pub(crate) fn to_string(rows: &[Vec<String>]) -> String {
+    let quote = |cell: &String| {
+        if cell.contains(['\t', '\n', '"']) {
+            format!("\"{}\"", cell.replace('"', "\"\""))
+        } else {
+            cell.clone()
+        }
+    };
+    rows.iter()
+        .map(|row| row.iter().map(quote).collect::<Vec<_>>().join("\t"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(v: &[&[&str]]) -> Vec<Vec<String>> {
+        v.iter()
+            .map(|row| row.iter().map(|s| s.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("a\tb\n1\t2\n"), rows(&[&["a", "b"], &["1", "2"]]));
+        assert_eq!(parse("a\tb\r\n1\t\r\n"), rows(&[&["a", "b"], &["1", ""]]));
+        assert_eq!(
+            parse("a,b\n\"1,5\",\"say \"\"hi\"\"\""),
+            rows(&[&["a", "b"], &["1,5", "say \"hi\""]])
+        );
+        assert_eq!(parse("\"two\nlines\"\tx"), rows(&[&["two\nlines", "x"]]));
+        assert_eq!(parse("Hello, world"), rows(&[&["Hello, world"]]));
+        assert_eq!(parse("one\ntwo"), rows(&[&["one"], &["two"]]));
+        assert_eq!(parse(""), rows(&[]));
+        // quoting doesn't need a delimiter
+        assert_eq!(parse("\"two\nlines\"\n"), rows(&[&["two\nlines"]]));
+        assert_eq!(parse("\"a\nb\"\nc"), rows(&[&["a\nb"], &["c"]]));
+        assert_eq!(parse("\"Hello\" he said"), rows(&[&["\"Hello\" he said"]]));
+        assert_eq!(parse("\"open\nend"), rows(&[&["\"open"], &["end"]]));
+    }
+
+    #[test]
+    fn test_to_string() {
+        let r = rows(&[&["a", "b\tc"], &["say \"hi\"", "two\nlines"]]);
+        assert_eq!(
+            to_string(&r),
+            "a\t\"b\tc\"\n\"say \"\"hi\"\"\"\t\"two\nlines\""
+        );
+        assert_eq!(parse(&to_string(&r)), r);
+    }
+}